@@ -0,0 +1,162 @@
+//! Character encoding detection and transcoding to UTF-8.
+//!
+//! The encoding is taken from, in order: a byte order mark, the
+//! `Content-Type` charset, an in-document declaration (`<meta charset>`,
+//! `http-equiv`, or the XML prolog), and finally a statistical guess.
+
+use chardetng::{EncodingDetector, Iso2022JpDetection, Utf8Detection};
+use encoding_rs::{Encoding, UTF_8};
+use regex::bytes::Regex;
+
+/// Browsers only look for `<meta charset>` in the first 1024 bytes.
+const PRESCAN_BYTES: usize = 1024;
+
+lazy_static::lazy_static! {
+    static ref META_CHARSET: Regex = Regex::new(
+        r#"(?i)<meta[^>]+charset\s*=\s*["']?\s*([a-z0-9_:.\-]+)"#
+    ).unwrap();
+    static ref XML_ENCODING: Regex = Regex::new(
+        r#"(?i)^\s*<\?xml[^>]+encoding\s*=\s*["']([a-z0-9_:.\-]+)["']"#
+    ).unwrap();
+}
+
+/// Where the encoding of a document came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharsetSource {
+    Bom,
+    Header,
+    Document,
+    Detected,
+}
+
+impl CharsetSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CharsetSource::Bom => "byte order mark",
+            CharsetSource::Header => "HTTP header",
+            CharsetSource::Document => "in-document declaration",
+            CharsetSource::Detected => "detected",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Charset {
+    pub encoding: &'static Encoding,
+    pub source: CharsetSource,
+}
+
+impl Charset {
+    pub fn name(&self) -> &'static str {
+        self.encoding.name()
+    }
+}
+
+/// Charset parameter of a `Content-Type` value.
+fn header_charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        if !key.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(value.trim().trim_matches(['"', '\'']).as_bytes())
+    })
+}
+
+fn declared_charset(body: &[u8]) -> Option<&'static Encoding> {
+    let head = &body[..body.len().min(PRESCAN_BYTES)];
+    let label = XML_ENCODING.captures(head)
+        .or_else(|| META_CHARSET.captures(head))
+        .and_then(|caps| caps.get(1))?;
+    let encoding = Encoding::for_label(label.as_bytes())?;
+
+    // A document that could be read far enough to find the declaration is
+    // not UTF-16, whatever it claims (HTML spec, "prescan a byte stream").
+    if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE {
+        Some(UTF_8)
+    } else {
+        Some(encoding)
+    }
+}
+
+fn guess(body: &[u8], url: &str) -> &'static Encoding {
+    let host = url::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string));
+    let tld = host.as_deref().and_then(|h| h.rsplit('.').next()).map(str::as_bytes);
+
+    let mut detector = EncodingDetector::new(Iso2022JpDetection::Allow);
+    detector.feed(body, true);
+    detector.guess(tld, Utf8Detection::Allow)
+}
+
+/// Work out the encoding of `body`.
+pub fn detect(body: &[u8], content_type: Option<&str>, url: &str) -> Charset {
+    if let Some((encoding, _)) = Encoding::for_bom(body) {
+        return Charset { encoding, source: CharsetSource::Bom };
+    }
+    if let Some(encoding) = content_type.and_then(header_charset) {
+        return Charset { encoding, source: CharsetSource::Header };
+    }
+    if let Some(encoding) = declared_charset(body) {
+        return Charset { encoding, source: CharsetSource::Document };
+    }
+    Charset { encoding: guess(body, url), source: CharsetSource::Detected }
+}
+
+/// Transcode `body` to UTF-8. Malformed sequences become U+FFFD.
+pub fn decode(body: &[u8], content_type: Option<&str>, url: &str) -> (String, Charset) {
+    let charset = detect(body, content_type, url);
+    let (text, _, _) = charset.encoding.decode(body);
+    (text.into_owned(), charset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://example.com/";
+
+    #[test]
+    fn xml_prolog_labels_match_in_any_case() {
+        let latin1 = detect(b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a/>", None, URL);
+        assert_eq!((latin1.name(), latin1.source), ("windows-1252", CharsetSource::Document));
+
+        let sjis = detect(b"<?XML version='1.0' encoding='Shift_JIS'?><a/>", None, URL);
+        assert_eq!((sjis.name(), sjis.source), ("Shift_JIS", CharsetSource::Document));
+
+        let utf8 = detect(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?><a/>", None, URL);
+        assert_eq!(utf8.source, CharsetSource::Document);
+    }
+
+    #[test]
+    fn meta_charset_is_found_in_the_prescan_window() {
+        let charset = detect(b"<html><head><META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=KOI8-R\">", None, URL);
+        assert_eq!((charset.name(), charset.source), ("KOI8-R", CharsetSource::Document));
+
+        let mut late = vec![b' '; PRESCAN_BYTES];
+        late.extend_from_slice(b"<meta charset=\"koi8-r\">");
+        assert_eq!(detect(&late, None, URL).source, CharsetSource::Detected);
+    }
+
+    #[test]
+    fn bom_then_header_then_document_win() {
+        let declared = b"<meta charset=\"iso-8859-2\">";
+        let mut with_bom = b"\xef\xbb\xbf".to_vec();
+        with_bom.extend_from_slice(declared);
+
+        assert_eq!(detect(&with_bom, Some("text/html; charset=latin1"), URL).source, CharsetSource::Bom);
+        let header = detect(declared, Some("text/html; charset=\"latin1\""), URL);
+        assert_eq!((header.name(), header.source), ("windows-1252", CharsetSource::Header));
+        assert_eq!(detect(declared, Some("text/html"), URL).name(), "ISO-8859-2");
+    }
+
+    #[test]
+    fn declared_utf16_is_read_as_utf8() {
+        assert_eq!(detect(b"<meta charset=\"utf-16\">", None, URL).encoding, UTF_8);
+    }
+
+    #[test]
+    fn decodes_to_utf8() {
+        let (text, charset) = decode(b"caf\xe9", Some("text/plain; charset=iso-8859-1"), URL);
+        assert_eq!((text.as_str(), charset.name()), ("café", "windows-1252"));
+    }
+}
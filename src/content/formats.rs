@@ -0,0 +1,477 @@
+//! Content-type dispatch for fetched documents, and renderers for the
+//! non-HTML text formats: JSON is pretty-printed, XML indented, CSV laid out
+//! as an aligned table, and plain text or source code passed through as is.
+
+use thiserror::Error;
+
+/// CSV columns wider than this are not padded further, so one long cell
+/// does not push every row far to the right.
+const MAX_PADDED_COLUMN: usize = 40;
+/// How much of an untyped body is inspected when sniffing.
+pub const SNIFF_BYTES: usize = 1024;
+/// Largest HTML or text document `open` will download, overridable with
+/// `MAX_PAGE_BYTES`. PDFs have their own cap.
+const DEFAULT_MAX_PAGE_BYTES: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum ContentError {
+    #[error("❌ Cannot open binary content: {mime} ({}). Only HTML, PDF and text formats are supported.", describe_size(*.size))]
+    Binary { mime: String, size: Option<usize> },
+    #[error("❌ Page is too large: over {limit} bytes.")]
+    TooLarge { limit: usize },
+}
+
+fn describe_size(size: Option<usize>) -> String {
+    match size {
+        Some(size) => format!("{} bytes", size),
+        None => "size unknown".to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextKind {
+    /// Plain text and source code, shown verbatim.
+    Plain,
+    Json,
+    Xml,
+    /// Delimiter-separated values (CSV or TSV).
+    Delimited(u8),
+}
+
+impl TextKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            TextKind::Plain => "plain text",
+            TextKind::Json => "JSON",
+            TextKind::Xml => "XML",
+            TextKind::Delimited(b'\t') => "TSV",
+            TextKind::Delimited(_) => "CSV",
+        }
+    }
+}
+
+/// What a fetched body should be treated as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Html,
+    Pdf,
+    Text(TextKind),
+}
+
+/// MIME type without parameters, lowercased: `Text/HTML; charset=utf-8`
+/// becomes `text/html`.
+pub fn essence(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or("").trim().to_lowercase()
+}
+
+/// Classify a declared MIME type. `None` means the type says nothing useful
+/// (missing, `application/octet-stream`, `text/plain`) and the URL and body
+/// should decide.
+fn kind_from_mime(mime: &str) -> Option<Result<DocumentKind, ()>> {
+    let kind = match mime {
+        "" | "application/octet-stream" | "binary/octet-stream" | "text/plain" => return None,
+        "text/html" | "application/xhtml+xml" => DocumentKind::Html,
+        "application/pdf" | "application/x-pdf" => DocumentKind::Pdf,
+        "application/json" | "text/json" | "application/ld+json" => DocumentKind::Text(TextKind::Json),
+        "application/xml" | "text/xml" => DocumentKind::Text(TextKind::Xml),
+        "text/csv" | "application/csv" => DocumentKind::Text(TextKind::Delimited(b',')),
+        "text/tab-separated-values" => DocumentKind::Text(TextKind::Delimited(b'\t')),
+        "application/javascript" | "application/x-javascript" | "application/ecmascript"
+        | "application/typescript" | "application/x-sh" | "application/x-httpd-php"
+        | "application/toml" | "application/yaml" | "application/x-yaml" | "application/sql"
+        | "application/graphql" | "application/x-tex" | "message/rfc822" => DocumentKind::Text(TextKind::Plain),
+        _ if mime.ends_with("+json") => DocumentKind::Text(TextKind::Json),
+        _ if mime.ends_with("+xml") => DocumentKind::Text(TextKind::Xml),
+        _ if mime.starts_with("text/") => DocumentKind::Text(TextKind::Plain),
+        _ if is_binary_mime(mime) => return Some(Err(())),
+        _ => return None,
+    };
+    Some(Ok(kind))
+}
+
+fn is_binary_mime(mime: &str) -> bool {
+    const BINARY_APPLICATION_TYPES: &[&str] = &[
+        "zip", "gzip", "x-gzip", "x-tar", "x-bzip2", "x-xz", "x-7z-compressed", "x-rar-compressed",
+        "vnd.rar", "zstd", "java-archive", "x-msdownload", "x-executable", "x-mach-binary",
+        "wasm", "vnd.android.package-archive", "x-iso9660-image", "msword", "vnd.ms-excel",
+        "vnd.ms-powerpoint", "x-shockwave-flash", "vnd.sqlite3", "x-protobuf", "x-font-ttf",
+    ];
+
+    ["image/", "audio/", "video/", "font/", "model/"].iter().any(|prefix| mime.starts_with(prefix))
+        || mime.strip_prefix("application/").is_some_and(|sub| {
+            BINARY_APPLICATION_TYPES.contains(&sub) || sub.starts_with("vnd.openxmlformats-")
+        })
+}
+
+pub fn max_bytes() -> usize {
+    std::env::var("MAX_PAGE_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_PAGE_BYTES)
+}
+
+/// Whether a declared `Content-Type` is binary, so the body need not be
+/// downloaded at all.
+pub fn is_declared_binary(content_type: Option<&str>) -> bool {
+    content_type.is_some_and(|ct| matches!(kind_from_mime(&essence(ct)), Some(Err(()))))
+}
+
+fn kind_from_extension(url: &str) -> Option<DocumentKind> {
+    let path = url::Url::parse(url).map(|u| u.path().to_string()).unwrap_or_default();
+    let extension = path.rsplit_once('.')?.1.to_lowercase();
+
+    let kind = match extension.as_str() {
+        "html" | "htm" | "xhtml" => DocumentKind::Html,
+        "pdf" => DocumentKind::Pdf,
+        "json" | "geojson" | "jsonld" | "ipynb" => DocumentKind::Text(TextKind::Json),
+        "xml" | "rss" | "atom" | "svg" | "xsd" | "wsdl" | "plist" => DocumentKind::Text(TextKind::Xml),
+        "csv" => DocumentKind::Text(TextKind::Delimited(b',')),
+        "tsv" | "tab" => DocumentKind::Text(TextKind::Delimited(b'\t')),
+        "txt" | "md" | "markdown" | "rst" | "adoc" | "org" | "tex" | "log" | "ini" | "cfg" | "conf"
+        | "toml" | "yaml" | "yml" | "rs" | "py" | "js" | "mjs" | "ts" | "tsx" | "jsx" | "go" | "c"
+        | "h" | "cc" | "cpp" | "hpp" | "java" | "kt" | "swift" | "rb" | "php" | "cs" | "sh" | "bash"
+        | "zsh" | "ps1" | "sql" | "lua" | "pl" | "r" | "scala" | "hs" | "ex" | "exs" | "erl" | "clj"
+        | "dart" | "zig" | "nim" | "vue" | "svelte" | "css" | "scss" | "less" | "proto" | "graphql"
+        | "diff" | "patch" | "lock" | "gradle" | "cmake" | "mk" | "dockerfile" | "gitignore" => {
+            DocumentKind::Text(TextKind::Plain)
+        }
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// MIME type of well-known binary formats, from their magic bytes.
+fn sniff_binary(body: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"\x00\x00\x01\x00", "image/x-icon"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"BZh", "application/x-bzip2"),
+        (b"\xfd7zXZ\x00", "application/x-xz"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"Rar!\x1a\x07", "application/vnd.rar"),
+        (b"\x28\xb5\x2f\xfd", "application/zstd"),
+        (b"\x7fELF", "application/x-executable"),
+        (b"\x00asm", "application/wasm"),
+        (b"ID3", "audio/mpeg"),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"\x1a\x45\xdf\xa3", "video/webm"),
+        (b"wOFF", "font/woff"),
+        (b"wOF2", "font/woff2"),
+    ];
+
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(magic, _)| body.starts_with(magic)) {
+        return Some(mime);
+    }
+    match body.get(..12) {
+        Some([b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P']) => Some("image/webp"),
+        Some([b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E']) => Some("audio/wav"),
+        Some([_, _, _, _, b'f', b't', b'y', b'p', ..]) => Some("video/mp4"),
+        _ => None,
+    }
+}
+
+/// Text has no NUL bytes and few control characters near the start.
+/// UTF-16 is full of NULs, so a UTF-16 byte order mark settles it.
+fn looks_like_text(body: &[u8]) -> bool {
+    if body.starts_with(b"\xff\xfe") || body.starts_with(b"\xfe\xff") {
+        return true;
+    }
+    let head = &body[..body.len().min(SNIFF_BYTES)];
+    let controls = head.iter()
+        .filter(|b| matches!(b, 0x00..=0x08 | 0x0e..=0x1a | 0x1c..=0x1f))
+        .count();
+    !head.contains(&0) && controls * 20 <= head.len()
+}
+
+/// Guess the kind of an untyped text body from how it starts.
+fn sniff_text(body: &[u8]) -> DocumentKind {
+    let head = String::from_utf8_lossy(&body[..body.len().min(SNIFF_BYTES)]).to_lowercase();
+    let start = head.trim_start_matches('\u{feff}').trim_start();
+
+    if start.starts_with("<!doctype html") || start.starts_with("<html") || head.contains("<body") || head.contains("<head") {
+        DocumentKind::Html
+    } else if start.starts_with("<?xml") || start.starts_with("<rss") || start.starts_with("<feed") {
+        DocumentKind::Text(TextKind::Xml)
+    } else if (start.starts_with('{') || start.starts_with('['))
+        && serde_json::from_slice::<serde_json::Value>(body).is_ok()
+    {
+        DocumentKind::Text(TextKind::Json)
+    } else {
+        DocumentKind::Text(TextKind::Plain)
+    }
+}
+
+/// What the `Content-Type`, or failing that the URL's file extension, says
+/// a response is, before any of the body is seen.
+pub fn declared_kind(content_type: Option<&str>, url: &str) -> Option<DocumentKind> {
+    match kind_from_mime(&content_type.map(essence).unwrap_or_default()) {
+        Some(kind) => kind.ok(),
+        None => kind_from_extension(url),
+    }
+}
+
+/// Decide how to treat a response from its `Content-Type`, the URL's file
+/// extension and, when both are inconclusive, the body itself.
+pub fn detect(content_type: Option<&str>, url: &str, body: &[u8]) -> Result<DocumentKind, ContentError> {
+    let mime = content_type.map(essence).unwrap_or_default();
+
+    if super::pdf::has_signature(body) {
+        return Ok(DocumentKind::Pdf);
+    }
+
+    match kind_from_mime(&mime) {
+        Some(Ok(kind)) => return Ok(kind),
+        Some(Err(())) => return Err(ContentError::Binary { mime, size: Some(body.len()) }),
+        None => {}
+    }
+    reject_binary(&mime, body, Some(body.len()))?;
+
+    // A declared text/plain is trusted over content sniffing
+    Ok(kind_from_extension(url).unwrap_or_else(|| match mime.as_str() {
+        "text/plain" => DocumentKind::Text(TextKind::Plain),
+        _ => sniff_text(body),
+    }))
+}
+
+/// Check the first `SNIFF_BYTES` of a body the way `detect` will check all
+/// of it, so a binary served under a generic type is rejected before the
+/// rest is downloaded. `size` is the declared length, if any.
+pub fn check_head(content_type: Option<&str>, head: &[u8], size: Option<usize>) -> Result<(), ContentError> {
+    let mime = content_type.map(essence).unwrap_or_default();
+    if super::pdf::has_signature(head) {
+        return Ok(());
+    }
+    match kind_from_mime(&mime) {
+        Some(Ok(_)) => Ok(()),
+        Some(Err(())) => Err(ContentError::Binary { mime, size }),
+        None => reject_binary(&mime, head, size),
+    }
+}
+
+/// Error out if a body of inconclusive type starts like a binary.
+fn reject_binary(mime: &str, body: &[u8], size: Option<usize>) -> Result<(), ContentError> {
+    if let Some(sniffed) = sniff_binary(body) {
+        return Err(ContentError::Binary { mime: sniffed.to_string(), size });
+    }
+    if !looks_like_text(body) {
+        let mime = if mime.is_empty() { "application/octet-stream" } else { mime };
+        return Err(ContentError::Binary { mime: mime.to_string(), size });
+    }
+    Ok(())
+}
+
+/// Render a non-HTML text document. Input that does not parse as its
+/// declared format is shown verbatim rather than rejected.
+pub fn render_text(kind: TextKind, text: &str) -> String {
+    match kind {
+        TextKind::Plain => text.to_string(),
+        TextKind::Json => pretty_json(text).unwrap_or_else(|| text.to_string()),
+        TextKind::Xml => indent_xml(text),
+        TextKind::Delimited(delimiter) => delimited_table(text, delimiter).unwrap_or_else(|| text.to_string()),
+    }
+}
+
+fn pretty_json(text: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(text.trim_start_matches('\u{feff}')).ok()?;
+    serde_json::to_string_pretty(&value).ok()
+}
+
+/// Lay CSV/TSV out as an aligned table with a rule under the header row.
+fn delimited_table(text: &str, delimiter: u8) -> Option<String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for record in reader.records() {
+        let record = record.ok()?;
+        rows.push(record.iter().map(|cell| cell.replace(['\r', '\n'], " ")).collect());
+    }
+    if rows.is_empty() {
+        return None;
+    }
+
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut widths = vec![0; columns];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count().min(MAX_PADDED_COLUMN));
+        }
+    }
+
+    let format_row = |row: &[String]| {
+        let mut cells: Vec<String> = (0..columns)
+            .map(|i| {
+                let cell = row.get(i).map(String::as_str).unwrap_or("");
+                let padding = widths[i].saturating_sub(cell.chars().count());
+                format!("{}{}", cell, " ".repeat(padding))
+            })
+            .collect();
+        // Short rows end at their last cell, not at a dangling separator
+        while cells.last().is_some_and(|cell| cell.trim().is_empty()) {
+            cells.pop();
+        }
+        cells.join(" | ").trim_end().to_string()
+    };
+
+    let mut lines = vec![format_row(&rows[0])];
+    lines.push(widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>().join("-+-"));
+    lines.extend(rows[1..].iter().map(|row| format_row(row)));
+    Some(lines.join("\n"))
+}
+
+/// Re-indent XML two spaces per level. Elements holding only text stay on
+/// one line; comments, CDATA and processing instructions are kept intact.
+fn indent_xml(text: &str) -> String {
+    let mut tokens: Vec<&str> = Vec::new();
+    let mut rest = text.trim_start_matches('\u{feff}');
+
+    while !rest.is_empty() {
+        let (token, remainder) = if rest.starts_with("<!--") {
+            split_after(rest, "-->")
+        } else if rest.starts_with("<![CDATA[") {
+            split_after(rest, "]]>")
+        } else if rest.starts_with('<') {
+            split_after(rest, ">")
+        } else {
+            rest.split_at(rest.find('<').unwrap_or(rest.len()))
+        };
+        tokens.push(token);
+        rest = remainder;
+    }
+
+    let is_open = |t: &str| {
+        t.starts_with('<') && !t.starts_with("</") && !t.starts_with("<?") && !t.starts_with("<!") && !t.ends_with("/>")
+    };
+
+    let mut out = Vec::new();
+    let mut depth: usize = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i].trim();
+        if token.is_empty() {
+            i += 1;
+            continue;
+        }
+
+        // <tag>text</tag> on one line
+        if is_open(token) && i + 2 < tokens.len() && !tokens[i + 1].starts_with('<') && tokens[i + 2].starts_with("</") {
+            out.push(format!("{}{}{}{}", "  ".repeat(depth), token, tokens[i + 1].trim(), tokens[i + 2].trim()));
+            i += 3;
+            continue;
+        }
+
+        if token.starts_with("</") {
+            depth = depth.saturating_sub(1);
+        }
+        for line in token.lines() {
+            out.push(format!("{}{}", "  ".repeat(depth), line.trim()));
+        }
+        if is_open(token) {
+            depth += 1;
+        }
+        i += 1;
+    }
+
+    out.join("\n")
+}
+
+/// Split `s` just after the first `end`, or at its end if `end` is missing.
+fn split_after<'a>(s: &'a str, end: &str) -> (&'a str, &'a str) {
+    match s.find(end) {
+        Some(i) => s.split_at(i + end.len()),
+        None => (s, ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://example.com/data";
+
+    #[test]
+    fn detect_trusts_declared_types() {
+        assert_eq!(detect(Some("text/html; charset=utf-8"), URL, b"{}").unwrap(), DocumentKind::Html);
+        assert_eq!(detect(Some("application/vnd.api+json"), URL, b"{}").unwrap(), DocumentKind::Text(TextKind::Json));
+        assert_eq!(detect(Some("text/tab-separated-values"), URL, b"a\tb").unwrap(), DocumentKind::Text(TextKind::Delimited(b'\t')));
+        assert!(matches!(detect(Some("image/png"), URL, b"abc"), Err(ContentError::Binary { .. })));
+    }
+
+    #[test]
+    fn detect_falls_back_to_extension_then_body() {
+        let octet = Some("application/octet-stream");
+        assert_eq!(detect(octet, "https://example.com/a.csv", b"a,b").unwrap(), DocumentKind::Text(TextKind::Delimited(b',')));
+        assert_eq!(detect(octet, URL, b"  <!DOCTYPE html><html>").unwrap(), DocumentKind::Html);
+        assert_eq!(detect(None, URL, b"<?xml version=\"1.0\"?><a/>").unwrap(), DocumentKind::Text(TextKind::Xml));
+        assert_eq!(detect(None, URL, b"[1, 2]").unwrap(), DocumentKind::Text(TextKind::Json));
+        assert_eq!(detect(None, URL, b"[not json").unwrap(), DocumentKind::Text(TextKind::Plain));
+        // A declared text/plain is not second-guessed from the body
+        assert_eq!(detect(Some("text/plain"), URL, b"<html>").unwrap(), DocumentKind::Text(TextKind::Plain));
+    }
+
+    #[test]
+    fn detect_sniffs_signatures() {
+        assert_eq!(detect(Some("text/html"), URL, b"%PDF-1.7").unwrap(), DocumentKind::Pdf);
+        match detect(None, URL, b"\x89PNG\r\n\x1a\n....") {
+            Err(ContentError::Binary { mime, size }) => assert_eq!((mime.as_str(), size), ("image/png", Some(12))),
+            other => panic!("expected binary, got {:?}", other),
+        }
+        assert!(matches!(detect(None, URL, b"ab\x00cd"), Err(ContentError::Binary { .. })));
+        assert_eq!(detect(None, URL, b"\xff\xfeh\x00i\x00").unwrap(), DocumentKind::Text(TextKind::Plain));
+    }
+
+    #[test]
+    fn head_check_rejects_binaries_early() {
+        let png = b"\x89PNG\r\n\x1a\n....";
+        match check_head(Some("application/octet-stream"), png, Some(5000)) {
+            Err(ContentError::Binary { mime, size }) => assert_eq!((mime.as_str(), size), ("image/png", Some(5000))),
+            other => panic!("expected binary, got {:?}", other),
+        }
+        assert!(check_head(None, b"ab\x00cd", None).is_err());
+        assert!(check_head(Some("image/gif"), b"GIF89a", None).is_err());
+        // Declared text and PDFs are left to `detect`
+        assert!(check_head(Some("text/html"), b"ab\x00cd", None).is_ok());
+        assert!(check_head(Some("application/octet-stream"), b"%PDF-1.7", None).is_ok());
+        assert!(check_head(None, b"plain words", None).is_ok());
+    }
+
+    #[test]
+    fn xml_is_indented_with_text_elements_on_one_line() {
+        let xml = "<?xml version=\"1.0\"?><feed><!-- a > b --><entry><title>Hi</title><link href=\"x\"/></entry></feed>";
+        assert_eq!(
+            indent_xml(xml),
+            "<?xml version=\"1.0\"?>\n<feed>\n  <!-- a > b -->\n  <entry>\n    <title>Hi</title>\n    <link href=\"x\"/>\n  </entry>\n</feed>"
+        );
+    }
+
+    #[test]
+    fn delimited_text_becomes_an_aligned_table() {
+        let table = delimited_table("name,qty\n\"Widget, large\",3\nNut,12,extra\n", b',').unwrap();
+        assert_eq!(
+            table,
+            "name          | qty\n--------------+-----+------\nWidget, large | 3\nNut           | 12  | extra"
+        );
+    }
+
+    #[test]
+    fn long_cells_do_not_widen_columns_past_the_cap() {
+        let long = "x".repeat(MAX_PADDED_COLUMN + 10);
+        let table = delimited_table(&format!("a,b\n{},1\n", long), b',').unwrap();
+        assert_eq!(table.lines().nth(1).unwrap(), format!("{}-+--", "-".repeat(MAX_PADDED_COLUMN)));
+    }
+
+    #[test]
+    fn unparseable_input_is_shown_verbatim() {
+        assert_eq!(render_text(TextKind::Json, "{oops"), "{oops");
+        assert_eq!(render_text(TextKind::Json, "{\"a\":1}"), "{\n  \"a\": 1\n}");
+    }
+}
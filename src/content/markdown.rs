@@ -0,0 +1,476 @@
+//! HTML to Markdown conversion that keeps document structure: ATX
+//! headings, fenced code blocks with language hints, GitHub-style tables,
+//! nested lists and reference-style links. Code blocks and tables are never
+//! wrapped; prose is wrapped to the requested width, if any.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Html, Node, Selector};
+use url::Url;
+
+use super::tables::table_rows;
+
+/// Elements whose content is never rendered.
+const SKIPPED_TAGS: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "iframe", "svg", "canvas",
+    "button", "input", "select", "textarea", "form", "dialog",
+];
+
+/// Elements that start a new block rather than continuing inline text.
+const BLOCK_TAGS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "body", "center", "dd", "details", "div", "dl",
+    "dt", "fieldset", "figcaption", "figure", "footer", "h1", "h2", "h3", "h4", "h5", "h6",
+    "header", "hr", "html", "li", "main", "nav", "ol", "p", "pre", "section", "summary",
+    "table", "ul",
+];
+
+/// Stands in for `<br>` in inline text until paragraphs are laid out, since
+/// newlines in the source are plain whitespace.
+const LINE_BREAK: char = '\u{2028}';
+
+/// Convert `html` (a full document or a fragment) to Markdown, wrapping
+/// prose at `width` columns, or not at all when `width` is `None`. Link and
+/// image targets are made absolute against `page_url` (or the document's
+/// `<base href>`), so they still work outside the page.
+pub fn from_html(html: &str, width: Option<usize>, page_url: &str) -> String {
+    let document = Html::parse_document(html);
+    let page_base = Url::parse(page_url).ok();
+    let base = Selector::parse("base[href]").ok()
+        .and_then(|selector| document.select(&selector).next())
+        .and_then(|element| element.value().attr("href"))
+        .and_then(|href| match &page_base {
+            Some(page) => page.join(href.trim()).ok(),
+            None => Url::parse(href.trim()).ok(),
+        })
+        .or(page_base);
+    let mut converter = Converter { links: Vec::new(), base };
+
+    let mut out = converter.blocks(*document.root_element(), width).join("\n\n");
+
+    if !converter.links.is_empty() {
+        out.push_str("\n\n");
+        for (i, link) in converter.links.iter().enumerate() {
+            out.push_str(&format!("[{}]: {}\n", i + 1, link));
+        }
+    }
+    out.trim_end().to_string() + "\n"
+}
+
+struct Converter {
+    /// Link and image targets in order of first appearance; a target's
+    /// reference number is its index plus one.
+    links: Vec<String>,
+    /// URL relative targets are resolved against.
+    base: Option<Url>,
+}
+
+impl Converter {
+    fn link_ref(&mut self, target: &str) -> usize {
+        let resolved = self.base.as_ref().and_then(|base| base.join(target).ok());
+        let target = resolved.as_ref().map_or(target, Url::as_str);
+        match self.links.iter().position(|l| l == target) {
+            Some(i) => i + 1,
+            None => {
+                self.links.push(target.to_string());
+                self.links.len()
+            }
+        }
+    }
+
+    /// Render the children of `node` as a list of blocks. Runs of inline
+    /// content between block elements become paragraphs.
+    fn blocks(&mut self, node: NodeRef<Node>, width: Option<usize>) -> Vec<String> {
+        let mut blocks = Vec::new();
+        let mut inline = String::new();
+
+        for child in node.children() {
+            match child.value() {
+                Node::Text(text) => inline.push_str(text),
+                Node::Element(element) if SKIPPED_TAGS.contains(&element.name()) => {}
+                Node::Element(element) if BLOCK_TAGS.contains(&element.name()) || contains_block(child) => {
+                    flush_paragraph(&mut inline, width, &mut blocks);
+                    let element = ElementRef::wrap(child).expect("element node");
+                    blocks.extend(self.block(element, width));
+                }
+                Node::Element(_) => {
+                    let element = ElementRef::wrap(child).expect("element node");
+                    let rendered = self.inline(element);
+                    inline.push_str(&rendered);
+                }
+                _ => {}
+            }
+        }
+        flush_paragraph(&mut inline, width, &mut blocks);
+        blocks
+    }
+
+    fn block(&mut self, element: ElementRef, width: Option<usize>) -> Vec<String> {
+        let name = element.value().name();
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                let text = collapse_whitespace(&self.inline_children(*element)).replace(LINE_BREAK, " ");
+                if text.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![format!("{} {}", "#".repeat(level), text)]
+                }
+            }
+            "pre" => vec![code_block(element)],
+            "ul" | "ol" => self.list(element, width).into_iter().collect(),
+            "blockquote" => {
+                let inner = self.blocks(*element, width.map(|w| w.saturating_sub(2))).join("\n\n");
+                if inner.is_empty() {
+                    return Vec::new();
+                }
+                let quoted = inner.lines()
+                    .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                vec![quoted]
+            }
+            "table" => self.table(element).into_iter().collect(),
+            "hr" => vec!["---".to_string()],
+            _ => self.blocks(*element, width),
+        }
+    }
+
+    fn list(&mut self, list: ElementRef, width: Option<usize>) -> Option<String> {
+        let ordered = list.value().name() == "ol";
+        let mut number = list.value().attr("start").and_then(|s| s.parse::<usize>().ok()).unwrap_or(1);
+        let mut items = Vec::new();
+
+        for child in list.children() {
+            let Some(item) = ElementRef::wrap(child) else { continue };
+            if item.value().name() != "li" {
+                // Stray nested lists directly inside a list
+                if matches!(item.value().name(), "ul" | "ol") {
+                    items.extend(self.list(item, width).map(|nested| indent(&nested, "  ")));
+                }
+                continue;
+            }
+
+            let marker = if ordered { format!("{}. ", number) } else { "- ".to_string() };
+            number += 1;
+
+            let body = self.blocks(*item, width.map(|w| w.saturating_sub(marker.len()))).join("\n");
+            let padding = " ".repeat(marker.len());
+            let mut lines = body.lines();
+            let mut rendered = format!("{}{}", marker, lines.next().unwrap_or("").trim_start());
+            for line in lines {
+                rendered.push('\n');
+                if !line.is_empty() {
+                    rendered.push_str(&padding);
+                    rendered.push_str(line);
+                }
+            }
+            items.push(rendered.trim_end().to_string());
+        }
+
+        (!items.is_empty()).then(|| items.join("\n"))
+    }
+
+    fn table(&mut self, table: ElementRef) -> Option<String> {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+
+        for row in table_rows(table) {
+            let mut cells = Vec::new();
+            for cell in row.children().filter_map(ElementRef::wrap) {
+                let name = cell.value().name();
+                if name != "td" && name != "th" {
+                    continue;
+                }
+                let text = collapse_whitespace(&self.inline_children(*cell))
+                    .replace(LINE_BREAK, " ")
+                    .replace('|', "\\|");
+                let span = cell.value().attr("colspan").and_then(|s| s.parse::<usize>().ok()).unwrap_or(1).clamp(1, 100);
+                cells.push(text);
+                cells.extend(std::iter::repeat_n(String::new(), span - 1));
+            }
+            if cells.is_empty() {
+                continue;
+            }
+            rows.push(cells);
+        }
+
+        if rows.is_empty() {
+            return None;
+        }
+
+        // GFM tables take exactly one header row: the first row, whether or
+        // not the source marked it up as one.
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut widths = vec![3; columns];
+        for row in &mut rows {
+            row.resize(columns, String::new());
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let format_row = |row: &[String]| {
+            let cells: Vec<String> = row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - cell.chars().count())))
+                .collect();
+            format!("| {} |", cells.join(" | "))
+        };
+
+        let mut lines = vec![format_row(&rows[0])];
+        lines.push(format!("| {} |", widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>().join(" | ")));
+        lines.extend(rows[1..].iter().map(|row| format_row(row)));
+        Some(lines.join("\n"))
+    }
+
+    fn inline_children(&mut self, node: NodeRef<Node>) -> String {
+        let mut out = String::new();
+        for child in node.children() {
+            match child.value() {
+                Node::Text(text) => out.push_str(text),
+                Node::Element(element) if SKIPPED_TAGS.contains(&element.name()) => {}
+                Node::Element(_) => {
+                    let element = ElementRef::wrap(child).expect("element node");
+                    let rendered = self.inline(element);
+                    out.push_str(&rendered);
+                }
+                _ => {}
+            }
+        }
+        out
+    }
+
+    fn inline(&mut self, element: ElementRef) -> String {
+        let name = element.value().name();
+        match name {
+            "br" => LINE_BREAK.to_string(),
+            "a" => {
+                let text = collapse_whitespace(&self.inline_children(*element));
+                match element.value().attr("href").map(str::trim) {
+                    Some(href) if !text.is_empty() && is_followable(href) => {
+                        format!("[{}][{}]", text, self.link_ref(href))
+                    }
+                    _ => text,
+                }
+            }
+            "img" => {
+                let alt = collapse_whitespace(element.value().attr("alt").unwrap_or(""));
+                match element.value().attr("src").map(str::trim) {
+                    Some(src) if !src.is_empty() && !src.starts_with("data:") => {
+                        format!("![{}][{}]", alt, self.link_ref(src))
+                    }
+                    _ => alt,
+                }
+            }
+            "code" | "kbd" | "samp" | "tt" => {
+                let text = collapse_whitespace(&element.text().collect::<String>());
+                if text.is_empty() {
+                    String::new()
+                } else if text.contains('`') {
+                    format!("`` {} ``", text)
+                } else {
+                    format!("`{}`", text)
+                }
+            }
+            "strong" | "b" => emphasize(&self.inline_children(*element), "**"),
+            "em" | "i" | "cite" => emphasize(&self.inline_children(*element), "*"),
+            "del" | "s" | "strike" => emphasize(&self.inline_children(*element), "~~"),
+            _ => self.inline_children(*element),
+        }
+    }
+}
+
+fn is_followable(href: &str) -> bool {
+    !href.is_empty() && !href.starts_with('#') && !href.starts_with("javascript:")
+}
+
+/// Wrap `text` in `marker` without swallowing the surrounding spaces, which
+/// Markdown would otherwise not treat as emphasis.
+fn emphasize(text: &str, marker: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_string();
+    }
+    let leading = if text.starts_with(char::is_whitespace) { " " } else { "" };
+    let trailing = if text.ends_with(char::is_whitespace) { " " } else { "" };
+    format!("{}{}{}{}{}", leading, marker, trimmed, marker, trailing)
+}
+
+fn code_block(pre: ElementRef) -> String {
+    let code = pre.children()
+        .filter_map(ElementRef::wrap)
+        .find(|child| child.value().name() == "code");
+
+    let language = code.iter()
+        .chain(std::iter::once(&pre))
+        .find_map(|el| code_language(*el))
+        .unwrap_or_default();
+
+    let text = pre.text().collect::<String>();
+    let text = text.strip_prefix('\n').unwrap_or(&text).trim_end();
+
+    // The fence must be longer than any backtick run inside the code
+    let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    format!("{}{}\n{}\n{}", fence, language, text, fence)
+}
+
+/// Language hint from `class="language-rust"`, `lang-rust`,
+/// `highlight-source-rust` or a `data-lang` attribute.
+fn code_language(element: ElementRef) -> Option<String> {
+    if let Some(lang) = element.value().attr("data-lang").or_else(|| element.value().attr("data-language")) {
+        return Some(lang.trim().to_string()).filter(|l| !l.is_empty());
+    }
+    element.value().classes().find_map(|class| {
+        ["language-", "lang-", "highlight-source-", "highlight-"]
+            .iter()
+            .find_map(|prefix| class.strip_prefix(prefix))
+            .filter(|lang| !lang.is_empty())
+            .map(str::to_string)
+    })
+}
+
+/// Collapse HTML whitespace runs to single spaces, keeping the explicit
+/// line breaks produced by `<br>`.
+fn collapse_whitespace(text: &str) -> String {
+    text.split(LINE_BREAK)
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join(&LINE_BREAK.to_string())
+        .trim_matches(|c: char| c.is_whitespace() || c == LINE_BREAK)
+        .to_string()
+}
+
+/// Inline elements wrapping block content (`<a><div>..</div></a>`,
+/// `<span><p>..</p></span>`) are laid out as blocks.
+fn contains_block(node: NodeRef<Node>) -> bool {
+    node.descendants().skip(1).any(|d| {
+        d.value().as_element().is_some_and(|e| BLOCK_TAGS.contains(&e.name()))
+    })
+}
+
+/// Turn accumulated inline content into a (wrapped) paragraph block.
+fn flush_paragraph(inline: &mut String, width: Option<usize>, blocks: &mut Vec<String>) {
+    let text = std::mem::take(inline);
+    let lines: Vec<String> = text
+        .split(LINE_BREAK)
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+
+    let mut paragraph = Vec::new();
+    for line in lines.iter().filter(|l| !l.is_empty()) {
+        match width {
+            Some(width) => paragraph.extend(wrap(line, width)),
+            None => paragraph.push(line.clone()),
+        }
+    }
+    if !paragraph.is_empty() {
+        blocks.push(paragraph.join("\n"));
+    }
+}
+
+/// Greedy word wrap. Words longer than `width` get a line to themselves.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let width = width.max(20);
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for word in line.split(' ') {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+fn indent(text: &str, prefix: &str) -> String {
+    text.lines()
+        .map(|line| if line.is_empty() { String::new() } else { format!("{}{}", prefix, line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "https://example.com/docs/guide.html";
+
+    fn md(html: &str) -> String {
+        from_html(html, None, PAGE)
+    }
+
+    #[test]
+    fn headings_and_paragraphs() {
+        assert_eq!(md("<h1>Title</h1><p>One\n two</p><h3>  Sub <em>part</em> </h3>"), "# Title\n\nOne two\n\n### Sub *part*\n");
+        assert_eq!(md("<h2></h2><p>Only</p>"), "Only\n");
+    }
+
+    #[test]
+    fn code_blocks_keep_language_and_content() {
+        let out = md("<pre><code class=\"language-rust\">fn main() {\n    println!(\"hi\");\n}\n</code></pre>");
+        assert_eq!(out, "```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n");
+
+        let out = md("<pre data-lang=\"sh\">echo ```</pre>");
+        assert_eq!(out, "````sh\necho ```\n````\n");
+        assert_eq!(md("<p>Run <code>cargo test</code> now</p>"), "Run `cargo test` now\n");
+    }
+
+    #[test]
+    fn tables_are_gfm_with_escaped_pipes() {
+        let out = md("<table><tr><th>Op</th><th>Meaning</th></tr><tr><td>a|b</td><td colspan=\"2\">or</td></tr></table>");
+        assert_eq!(out, "| Op   | Meaning |     |\n| ---- | ------- | --- |\n| a\\|b | or      |     |\n");
+    }
+
+    #[test]
+    fn nested_lists_are_indented_under_their_item() {
+        let out = md("<ul><li>One<ul><li>Inner</li></ul></li><li>Two</li></ul><ol start=\"3\"><li>Three</li><li>Four</li></ol>");
+        assert_eq!(out, "- One\n  - Inner\n- Two\n\n3. Three\n4. Four\n");
+    }
+
+    #[test]
+    fn links_become_references_resolved_against_the_page() {
+        let out = md("<p><a href=\"intro.html\">Intro</a>, <a href=\"/api\">API</a>, <a href=\"intro.html\">again</a>, \
+                      <a href=\"#top\">top</a> <img src=\"img/logo.png\" alt=\"Logo\"></p>");
+        assert_eq!(
+            out,
+            "[Intro][1], [API][2], [again][1], top ![Logo][3]\n\n\
+             [1]: https://example.com/docs/intro.html\n\
+             [2]: https://example.com/api\n\
+             [3]: https://example.com/docs/img/logo.png\n"
+        );
+    }
+
+    #[test]
+    fn base_href_overrides_the_page_url() {
+        let out = md("<html><head><base href=\"https://cdn.example.net/v2/\"></head><body><a href=\"a\">A</a></body></html>");
+        assert!(out.ends_with("[1]: https://cdn.example.net/v2/a\n"), "{}", out);
+        assert!(from_html("<a href=\"a\">A</a>", None, "not a url").ends_with("[1]: a\n"));
+    }
+
+    #[test]
+    fn wrap_modes() {
+        let text = "word ".repeat(12);
+        let html = format!("<p>{}</p><pre>{}</pre>", text, text);
+
+        let unwrapped = from_html(&html, None, PAGE);
+        assert_eq!(unwrapped.lines().next().unwrap(), text.trim());
+
+        let wrapped = from_html(&html, Some(24), PAGE);
+        let mut lines = wrapped.lines();
+        assert_eq!(lines.next(), Some("word word word word word"));
+        assert!(wrapped.lines().take_while(|l| !l.starts_with("```")).all(|l| l.chars().count() <= 24));
+        // Code is never wrapped
+        assert!(wrapped.contains(&format!("```\n{}\n```", text.trim_end())));
+
+        // Widths below 20 are raised to 20
+        let narrow = from_html(&format!("<p>{}</p>", text), Some(5), PAGE);
+        assert_eq!(narrow.lines().next(), Some("word word word word"));
+    }
+}
@@ -0,0 +1,392 @@
+//! Document metadata: `<title>`, meta description, canonical URL, language,
+//! Open Graph and Twitter card tags, publication dates, and JSON-LD
+//! structured data for articles, products and FAQs.
+
+use scraper::{Html, Selector};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use url::Url;
+
+/// JSON-LD `@type`s worth surfacing.
+const ARTICLE_TYPES: &[&str] = &[
+    "Article", "NewsArticle", "BlogPosting", "TechArticle", "ScholarlyArticle", "Report", "LiveBlogPosting",
+];
+const PRODUCT_TYPES: &[&str] = &["Product", "ProductGroup"];
+const FAQ_TYPES: &[&str] = &["FAQPage"];
+/// FAQ questions listed in the text summary.
+const MAX_FAQ_QUESTIONS: usize = 10;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PageMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    /// `og:*` properties, keyed without the prefix.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub open_graph: BTreeMap<String, String>,
+    /// `twitter:*` card fields, keyed without the prefix.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub twitter: BTreeMap<String, String>,
+    /// Article, Product and FAQPage objects from JSON-LD, as published.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub json_ld: Vec<Value>,
+}
+
+fn selector(css: &str) -> Selector {
+    Selector::parse(css).expect("static selector")
+}
+
+fn clean(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+fn meta_content(document: &Html, attribute: &str, name: &str) -> Option<String> {
+    document
+        .select(&selector("meta[content]"))
+        .find(|el| el.value().attr(attribute).is_some_and(|v| v.eq_ignore_ascii_case(name)))
+        .and_then(|el| clean(el.value().attr("content")?))
+}
+
+/// Meta tags whose `property` or `name` starts with `prefix`.
+fn prefixed_meta(document: &Html, prefix: &str) -> BTreeMap<String, String> {
+    let mut tags = BTreeMap::new();
+    for el in document.select(&selector("meta[content]")) {
+        let key = el.value().attr("property").or_else(|| el.value().attr("name")).unwrap_or("");
+        let Some(key) = key.to_lowercase().strip_prefix(prefix).map(str::to_string) else { continue };
+        if let Some(content) = el.value().attr("content").and_then(clean) {
+            // Repeated tags (several og:image) keep the first
+            tags.entry(key).or_insert(content);
+        }
+    }
+    tags
+}
+
+pub fn extract(html: &str, page_url: &str) -> PageMetadata {
+    let document = Html::parse_document(html);
+    let base = Url::parse(page_url).ok();
+
+    let open_graph = prefixed_meta(&document, "og:");
+    let twitter = prefixed_meta(&document, "twitter:");
+    let json_ld = json_ld(&document);
+
+    let ld_field = |field: &str| {
+        json_ld.iter()
+            .filter(|item| has_type(item, ARTICLE_TYPES))
+            .find_map(|item| item.get(field).and_then(Value::as_str).and_then(clean))
+    };
+
+    let title = document.select(&selector("title")).next()
+        .and_then(|t| clean(&t.text().collect::<String>()))
+        .or_else(|| open_graph.get("title").cloned());
+
+    let description = meta_content(&document, "name", "description")
+        .or_else(|| open_graph.get("description").cloned())
+        .or_else(|| twitter.get("description").cloned());
+
+    let canonical = document.select(&selector("link[rel][href]"))
+        .find(|el| {
+            el.value().attr("rel").unwrap_or("").split_whitespace().any(|r| r.eq_ignore_ascii_case("canonical"))
+        })
+        .and_then(|el| el.value().attr("href"))
+        .or_else(|| open_graph.get("url").map(String::as_str))
+        .and_then(|href| match &base {
+            Some(base) => base.join(href.trim()).ok().map(|u| u.to_string()),
+            None => clean(href),
+        });
+
+    let lang = document.root_element().value().attr("lang")
+        .and_then(clean)
+        .or_else(|| meta_content(&document, "http-equiv", "content-language"))
+        .or_else(|| open_graph.get("locale").cloned());
+
+    let published = meta_content(&document, "property", "article:published_time")
+        .or_else(|| ld_field("datePublished"))
+        .or_else(|| meta_content(&document, "itemprop", "datePublished"))
+        .or_else(|| meta_content(&document, "name", "date"));
+
+    let modified = meta_content(&document, "property", "article:modified_time")
+        .or_else(|| ld_field("dateModified"))
+        .or_else(|| open_graph.get("updated_time").cloned())
+        .or_else(|| meta_content(&document, "itemprop", "dateModified"));
+
+    PageMetadata { title, description, canonical, lang, published, modified, open_graph, twitter, json_ld }
+}
+
+/// Article, Product and FAQPage objects from every JSON-LD block, looking
+/// inside arrays and `@graph` containers. Malformed blocks are skipped.
+fn json_ld(document: &Html) -> Vec<Value> {
+    let mut found = Vec::new();
+    for script in document.select(&selector("script[type]")) {
+        if !script.value().attr("type").unwrap_or("").eq_ignore_ascii_case("application/ld+json") {
+            continue;
+        }
+        let raw = script.text().collect::<String>();
+        if let Ok(value) = serde_json::from_str::<Value>(raw.trim()) {
+            collect_typed(value, &mut found);
+        }
+    }
+    found
+}
+
+fn collect_typed(value: Value, found: &mut Vec<Value>) {
+    match value {
+        Value::Array(items) => items.into_iter().for_each(|item| collect_typed(item, found)),
+        Value::Object(mut object) => {
+            if let Some(graph) = object.remove("@graph") {
+                collect_typed(graph, found);
+            }
+            let value = Value::Object(object);
+            if [ARTICLE_TYPES, PRODUCT_TYPES, FAQ_TYPES].iter().any(|types| has_type(&value, types)) {
+                found.push(value);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn has_type(value: &Value, types: &[&str]) -> bool {
+    let matches = |t: &Value| t.as_str().is_some_and(|t| types.contains(&t.trim_start_matches("schema:")));
+    match value.get("@type") {
+        Some(Value::Array(list)) => list.iter().any(matches),
+        Some(t) => matches(t),
+        None => false,
+    }
+}
+
+/// A name from a string, an object with `name`, or a list of either.
+fn names(value: Option<&Value>) -> Option<String> {
+    let list: Vec<String> = match value? {
+        Value::Array(items) => items.iter().filter_map(|v| names(Some(v))).collect(),
+        Value::String(s) => clean(s).into_iter().collect(),
+        Value::Object(o) => o.get("name").and_then(Value::as_str).and_then(clean).into_iter().collect(),
+        _ => Vec::new(),
+    };
+    (!list.is_empty()).then(|| list.join(", "))
+}
+
+fn scalar(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(s) => clean(s),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Text inside HTML markup, as FAQ answers often carry it.
+fn strip_tags(text: &str) -> String {
+    let fragment = Html::parse_fragment(text);
+    clean(&fragment.root_element().text().collect::<String>()).unwrap_or_default()
+}
+
+impl PageMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none()
+            && self.canonical.is_none()
+            && self.lang.is_none()
+            && self.published.is_none()
+            && self.modified.is_none()
+            && self.open_graph.is_empty()
+            && self.twitter.is_empty()
+            && self.json_ld.is_empty()
+    }
+
+    /// Human-readable summary lines, one fact each.
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut push = |label: &str, value: &Option<String>| {
+            if let Some(value) = value {
+                lines.push(format!("{}: {}", label, value));
+            }
+        };
+        push("Description", &self.description);
+        push("Canonical", &self.canonical);
+        push("Language", &self.lang);
+        push("Published", &self.published);
+        push("Modified", &self.modified);
+
+        for (label, tags) in [("Open Graph", &self.open_graph), ("Twitter", &self.twitter)] {
+            // Title and description already appear above
+            let shown: Vec<String> = tags.iter()
+                .filter(|(key, _)| !matches!(key.as_str(), "title" | "description"))
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            if !shown.is_empty() {
+                lines.push(format!("{}: {}", label, shown.join(", ")));
+            }
+        }
+
+        for item in &self.json_ld {
+            lines.extend(summarize_json_ld(item));
+        }
+        lines
+    }
+}
+
+fn summarize_json_ld(item: &Value) -> Vec<String> {
+    if has_type(item, ARTICLE_TYPES) {
+        let mut parts = Vec::new();
+        if let Some(headline) = scalar(item.get("headline")).or_else(|| scalar(item.get("name"))) {
+            parts.push(headline);
+        }
+        if let Some(author) = names(item.get("author")) {
+            parts.push(format!("by {}", author));
+        }
+        if let Some(publisher) = names(item.get("publisher")) {
+            parts.push(format!("published by {}", publisher));
+        }
+        return vec![format!("JSON-LD Article: {}", parts.join(", "))];
+    }
+
+    if has_type(item, PRODUCT_TYPES) {
+        let mut parts: Vec<String> = scalar(item.get("name")).into_iter().collect();
+        if let Some(brand) = names(item.get("brand")) {
+            parts.push(format!("brand {}", brand));
+        }
+        let offer = match item.get("offers") {
+            Some(Value::Array(offers)) => offers.first(),
+            other => other,
+        };
+        if let Some(offer) = offer {
+            let price = scalar(offer.get("price")).or_else(|| scalar(offer.get("lowPrice")));
+            if let Some(price) = price {
+                let currency = scalar(offer.get("priceCurrency")).unwrap_or_default();
+                parts.push(format!("price {} {}", price, currency).trim_end().to_string());
+            }
+            if let Some(availability) = scalar(offer.get("availability")) {
+                parts.push(availability.rsplit('/').next().unwrap_or(&availability).to_string());
+            }
+        }
+        if let Some(rating) = item.get("aggregateRating") {
+            if let Some(value) = scalar(rating.get("ratingValue")) {
+                let count = scalar(rating.get("reviewCount")).or_else(|| scalar(rating.get("ratingCount")));
+                parts.push(match count {
+                    Some(count) => format!("rated {} ({} reviews)", value, count),
+                    None => format!("rated {}", value),
+                });
+            }
+        }
+        return vec![format!("JSON-LD Product: {}", parts.join(", "))];
+    }
+
+    if has_type(item, FAQ_TYPES) {
+        let questions: Vec<&Value> = match item.get("mainEntity") {
+            Some(Value::Array(entities)) => entities.iter().collect(),
+            Some(entity) => vec![entity],
+            None => Vec::new(),
+        };
+        let mut lines = vec![format!("JSON-LD FAQ: {} question(s)", questions.len())];
+        for question in questions.iter().take(MAX_FAQ_QUESTIONS) {
+            let Some(name) = scalar(question.get("name")) else { continue };
+            let answer = question.get("acceptedAnswer")
+                .and_then(|a| scalar(a.get("text")))
+                .map(|a| strip_tags(&a))
+                .unwrap_or_default();
+            lines.push(format!("Q: {} A: {}", name, answer).trim_end().to_string());
+        }
+        return lines;
+    }
+
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "https://example.com/blog/post";
+
+    fn page(head: &str) -> String {
+        format!("<html lang=\"en-GB\"><head>{}</head><body><p>Body</p></body></html>", head)
+    }
+
+    fn ld(json: &str) -> String {
+        format!("<script type=\"application/ld+json\">{}</script>", json)
+    }
+
+    #[test]
+    fn open_graph_and_twitter_fill_in_missing_fields() {
+        let metadata = extract(&page(
+            "<meta property=\"og:title\" content=\"OG title\">\
+             <meta name=\"twitter:description\" content=\"Card text\">\
+             <meta property=\"og:url\" content=\"/blog/post?ref=og\">\
+             <meta property=\"og:image\" content=\"a.png\"><meta property=\"og:image\" content=\"b.png\">",
+        ), PAGE);
+
+        assert_eq!(metadata.title.as_deref(), Some("OG title"));
+        assert_eq!(metadata.description.as_deref(), Some("Card text"));
+        assert_eq!(metadata.canonical.as_deref(), Some("https://example.com/blog/post?ref=og"));
+        assert_eq!(metadata.lang.as_deref(), Some("en-GB"));
+        assert_eq!(metadata.open_graph["image"], "a.png");
+        assert_eq!(metadata.twitter["description"], "Card text");
+    }
+
+    #[test]
+    fn page_tags_win_over_social_tags() {
+        let metadata = extract(&page(
+            "<title> Real  title </title><meta name=\"description\" content=\"Real description\">\
+             <meta property=\"og:title\" content=\"OG title\"><meta property=\"og:description\" content=\"OG text\">\
+             <link rel=\"alternate canonical\" href=\"../canonical\">",
+        ), PAGE);
+
+        assert_eq!(metadata.title.as_deref(), Some("Real title"));
+        assert_eq!(metadata.description.as_deref(), Some("Real description"));
+        assert_eq!(metadata.canonical.as_deref(), Some("https://example.com/canonical"));
+    }
+
+    #[test]
+    fn json_ld_is_found_in_arrays_and_graphs() {
+        let metadata = extract(&page(&format!(
+            "{}{}{}",
+            ld(r#"[{"@type": "BreadcrumbList"}, {"@type": "NewsArticle", "headline": "Big news", "datePublished": "2024-01-02"}]"#),
+            ld(r#"{"@context": "https://schema.org", "@graph": [{"@type": "WebSite"}, {"@type": ["Product"], "name": "Widget"}]}"#),
+            ld("{not json"),
+        )), PAGE);
+
+        let types: Vec<&Value> = metadata.json_ld.iter().map(|item| &item["@type"]).collect();
+        assert_eq!(types, [&Value::from("NewsArticle"), &serde_json::json!(["Product"])]);
+        assert_eq!(metadata.published.as_deref(), Some("2024-01-02"));
+    }
+
+    #[test]
+    fn products_and_faqs_are_summarized() {
+        let metadata = extract(&page(&format!(
+            "{}{}",
+            ld(r#"{"@type": "Product", "name": "Widget", "brand": {"name": "Acme"},
+                   "offers": [{"price": 9.5, "priceCurrency": "EUR", "availability": "https://schema.org/InStock"}],
+                   "aggregateRating": {"ratingValue": "4.5", "reviewCount": 12}}"#),
+            ld(r#"{"@type": "FAQPage", "mainEntity": [
+                   {"name": "Is it safe?", "acceptedAnswer": {"text": "<p>Yes, <b>very</b>.</p>"}},
+                   {"name": "Unanswered?"}]}"#),
+        )), PAGE);
+
+        let summary = metadata.summary();
+        assert!(summary.contains(&"JSON-LD Product: Widget, brand Acme, price 9.5 EUR, InStock, rated 4.5 (12 reviews)".to_string()), "{:?}", summary);
+        assert!(summary.contains(&"JSON-LD FAQ: 2 question(s)".to_string()));
+        assert!(summary.contains(&"Q: Is it safe? A: Yes, very.".to_string()), "{:?}", summary);
+        assert!(summary.contains(&"Q: Unanswered? A:".to_string()));
+    }
+
+    #[test]
+    fn faq_summaries_list_a_limited_number_of_questions() {
+        let questions: Vec<String> = (0..MAX_FAQ_QUESTIONS + 5)
+            .map(|i| format!(r#"{{"name": "Question {}?", "acceptedAnswer": {{"text": "Answer"}}}}"#, i))
+            .collect();
+        let metadata = extract(&page(&ld(&format!(r#"{{"@type": "FAQPage", "mainEntity": [{}]}}"#, questions.join(",")))), PAGE);
+
+        let summary = metadata.summary();
+        let faq = summary.iter().position(|line| line.starts_with("JSON-LD FAQ")).unwrap();
+        assert_eq!(summary[faq], format!("JSON-LD FAQ: {} question(s)", MAX_FAQ_QUESTIONS + 5));
+        assert_eq!(summary.len() - faq, 1 + MAX_FAQ_QUESTIONS);
+    }
+}
@@ -0,0 +1,181 @@
+//! Conversion of fetched documents into the line-numbered text that `open`
+//! shows and `find` searches.
+
+use std::str::FromStr;
+
+use self::formats::TextKind;
+
+pub mod encoding;
+pub mod formats;
+pub mod markdown;
+pub mod metadata;
+pub mod pdf;
+pub mod readability;
+pub mod select;
+pub mod tables;
+
+/// Wrap width used when a call does not ask for one.
+pub const DEFAULT_WRAP_WIDTH: usize = 80;
+/// Width handed to html2text for "no wrap"; wide enough that no real
+/// paragraph reaches it.
+const UNWRAPPED_WIDTH: usize = 100_000;
+
+/// How much of an HTML page `open` renders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentMode {
+    /// Only the main article body, Readability-style.
+    #[default]
+    Article,
+    /// The whole page, navigation and all.
+    Full,
+}
+
+impl ContentMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContentMode::Article => "article",
+            ContentMode::Full => "full",
+        }
+    }
+}
+
+impl FromStr for ContentMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "article" => Ok(ContentMode::Article),
+            "full" => Ok(ContentMode::Full),
+            other => Err(format!("❌ Invalid mode: '{}'. Use \"article\" or \"full\".", other)),
+        }
+    }
+}
+
+/// Output syntax for rendered pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Plain text via html2text; tables are drawn with box characters.
+    #[default]
+    Text,
+    /// Markdown that keeps headings, code fences, tables and lists intact.
+    Markdown,
+}
+
+impl OutputFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Markdown => "markdown",
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "text" | "plain" => Ok(OutputFormat::Text),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            other => Err(format!("❌ Invalid format: '{}'. Use \"text\" or \"markdown\".", other)),
+        }
+    }
+}
+
+/// How `open` should render a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub mode: ContentMode,
+    pub format: OutputFormat,
+    /// Column to wrap prose at; `None` keeps each paragraph on one line.
+    pub wrap: Option<usize>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            mode: ContentMode::default(),
+            format: OutputFormat::default(),
+            wrap: Some(DEFAULT_WRAP_WIDTH),
+        }
+    }
+}
+
+/// Text produced from a page, plus article details when they were found.
+#[derive(Debug, Clone, Default)]
+pub struct RenderedPage {
+    pub text: String,
+    /// The mode actually used; article mode falls back to full when no
+    /// article body is found.
+    pub mode: ContentMode,
+    pub title: Option<String>,
+    pub byline: Option<String>,
+    pub published: Option<String>,
+}
+
+/// A fetched document as kept in the session, so it can be re-rendered
+/// with different options without refetching. Rendering options only
+/// apply to HTML.
+#[derive(Debug, Clone)]
+pub enum Source {
+    Html(String),
+    /// JSON, XML, CSV, plain text or source code, as fetched.
+    Text { kind: TextKind, text: String },
+    /// Extracted text with page markers.
+    Pdf { text: String, pages: usize },
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Source::Html(String::new())
+    }
+}
+
+/// Render `source` as fetched from `page_url`, which relative links in
+/// Markdown output are resolved against.
+pub fn render(source: &Source, options: &RenderOptions, page_url: &str) -> Result<RenderedPage, String> {
+    match source {
+        Source::Html(html) => render_html(html, options, page_url),
+        Source::Text { kind, text } => Ok(RenderedPage {
+            text: formats::render_text(*kind, text),
+            mode: ContentMode::Full,
+            ..Default::default()
+        }),
+        Source::Pdf { text, .. } => Ok(RenderedPage {
+            text: text.clone(),
+            mode: ContentMode::Full,
+            ..Default::default()
+        }),
+    }
+}
+
+pub fn render_html(html: &str, options: &RenderOptions, page_url: &str) -> Result<RenderedPage, String> {
+    if options.mode == ContentMode::Article {
+        if let Some(article) = readability::extract(html) {
+            let text = convert(&article.content_html, options, page_url)?;
+            return Ok(RenderedPage {
+                text,
+                mode: ContentMode::Article,
+                title: article.title,
+                byline: article.byline,
+                published: article.published,
+            });
+        }
+    }
+
+    Ok(RenderedPage {
+        text: convert(html, options, page_url)?,
+        mode: ContentMode::Full,
+        ..Default::default()
+    })
+}
+
+fn convert(html: &str, options: &RenderOptions, page_url: &str) -> Result<String, String> {
+    match options.format {
+        OutputFormat::Markdown => Ok(markdown::from_html(html, options.wrap, page_url)),
+        OutputFormat::Text => html2text::config::plain()
+            .allow_width_overflow()
+            .string_from_read(html.as_bytes(), options.wrap.unwrap_or(UNWRAPPED_WIDTH))
+            .map_err(|e| format!("Error converting HTML to text: {}", e)),
+    }
+}
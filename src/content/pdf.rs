@@ -0,0 +1,174 @@
+//! PDF text extraction. Each page is introduced by a `--- page N ---`
+//! marker so line numbers, `find` and citations can refer to pages.
+//!
+//! The extractor panics on some malformed files. Those panics are caught
+//! here and reported as parse errors, which relies on the crate being built
+//! with `panic = "unwind"`; with `abort` a bad file would take down the
+//! whole process.
+
+use pdf_extract::OutputError;
+use thiserror::Error;
+
+/// Largest PDF `open` will download, overridable with `MAX_PDF_BYTES`.
+const DEFAULT_MAX_PDF_BYTES: usize = 20 * 1024 * 1024;
+/// Pages averaging fewer visible characters than this are treated as
+/// images of text.
+const MIN_CHARS_PER_PAGE: usize = 16;
+
+#[derive(Debug, Error)]
+pub enum PdfError {
+    #[error("❌ PDF is too large: {size} bytes (limit {limit} bytes).")]
+    TooLarge { size: usize, limit: usize },
+    #[error("❌ PDF is password-protected and cannot be read.")]
+    Encrypted,
+    #[error("❌ This PDF has no extractable text ({pages} page(s)). It is probably scanned or image-only, and OCR is not supported.")]
+    NoText { pages: usize },
+    #[error("❌ Could not read PDF: {0}")]
+    Parse(String),
+}
+
+/// Text of a PDF with page markers.
+#[derive(Debug, Clone)]
+pub struct PdfText {
+    pub text: String,
+    pub pages: usize,
+}
+
+pub fn max_bytes() -> usize {
+    std::env::var("MAX_PDF_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_PDF_BYTES)
+}
+
+/// Whether a response will be treated as a PDF judging by its
+/// `Content-Type` and URL alone, so the size cap can be enforced while
+/// downloading. Bodies that turn out to start with `%PDF-` are PDFs too.
+pub fn is_declared(content_type: Option<&str>, url: &str) -> bool {
+    super::formats::declared_kind(content_type, url) == Some(super::formats::DocumentKind::Pdf)
+}
+
+/// Whether `body` starts like a PDF, whatever it was served as.
+pub fn has_signature(body: &[u8]) -> bool {
+    body.starts_with(b"%PDF-")
+}
+
+/// Extract text from `bytes`. Parsing is CPU-bound, so callers should run
+/// this on a blocking thread.
+pub fn extract(bytes: &[u8]) -> Result<PdfText, PdfError> {
+    let limit = max_bytes();
+    if bytes.len() > limit {
+        return Err(PdfError::TooLarge { size: bytes.len(), limit });
+    }
+
+    let parsed = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(bytes))
+        .map_err(|_| PdfError::Parse("the parser crashed on this file".to_string()))?;
+    let pages = parsed.map_err(|e| match e {
+        OutputError::PdfError(pdf_extract::Error::Decryption(_)) => PdfError::Encrypted,
+        other => PdfError::Parse(other.to_string()),
+    })?;
+
+    let visible_chars: usize = pages.iter()
+        .map(|page| page.chars().filter(|c| !c.is_whitespace()).count())
+        .sum();
+    if pages.is_empty() || visible_chars < MIN_CHARS_PER_PAGE * pages.len() {
+        return Err(PdfError::NoText { pages: pages.len() });
+    }
+
+    let mut text = String::new();
+    for (i, page) in pages.iter().enumerate() {
+        text.push_str(&format!("--- page {} ---\n", i + 1));
+        text.push_str(&tidy_page(page));
+        text.push_str("\n\n");
+    }
+
+    Ok(PdfText { text: text.trim_end().to_string(), pages: pages.len() })
+}
+
+/// Trim trailing spaces and squeeze the long runs of blank lines the
+/// extractor leaves between text blocks.
+fn tidy_page(page: &str) -> String {
+    let mut out = Vec::new();
+    let mut blank_run = 0;
+
+    for line in page.lines().map(str::trim_end) {
+        if line.is_empty() {
+            blank_run += 1;
+            if blank_run > 1 || out.is_empty() {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        out.push(line);
+    }
+    out.join("\n").trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declared_pdfs_include_every_pdf_type_and_untyped_pdf_urls() {
+        assert!(is_declared(Some("application/pdf"), "https://example.com/doc"));
+        assert!(is_declared(Some("Application/X-PDF; name=a.pdf"), "https://example.com/doc"));
+        assert!(is_declared(Some("application/octet-stream"), "https://example.com/paper.pdf"));
+        assert!(is_declared(None, "https://example.com/paper.PDF"));
+        assert!(!is_declared(Some("text/html"), "https://example.com/paper.pdf"));
+        assert!(!is_declared(None, "https://example.com/paper"));
+    }
+
+    /// A one-page PDF showing `text` in font `font`, with a valid xref table.
+    fn pdf(text: &str, font: &str) -> Vec<u8> {
+        let content = format!("BT /F1 12 Tf 72 700 Td ({}) Tj ET", text);
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >>".to_string(),
+            format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content),
+            font.to_string(),
+        ];
+
+        let mut out = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::new();
+        for (i, body) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, body));
+        }
+        let xref = out.len();
+        out.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+        for offset in offsets {
+            out.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        out.push_str(&format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF", objects.len() + 1, xref));
+        out.into_bytes()
+    }
+
+    const HELVETICA: &str = "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>";
+
+    #[test]
+    fn pages_get_markers() {
+        let pdf = extract(&pdf("Hello there world, this is a test", HELVETICA)).unwrap();
+
+        assert_eq!(pdf.pages, 1);
+        assert_eq!(pdf.text, "--- page 1 ---\nHello there world, this is a test");
+    }
+
+    #[test]
+    fn pages_without_text_are_rejected() {
+        assert!(matches!(extract(&pdf("", HELVETICA)), Err(PdfError::NoText { pages: 1 })));
+    }
+
+    #[test]
+    fn parser_panics_become_errors() {
+        // A Type0 font whose descendant fonts are a number makes the parser panic
+        let broken = pdf("Hello there world", "<< /Type /Font /Subtype /Type0 /BaseFont /X /DescendantFonts 7 >>");
+        assert!(matches!(extract(&broken), Err(PdfError::Parse(_))));
+    }
+
+    #[test]
+    fn truncated_pdfs_are_errors() {
+        assert!(matches!(extract(b"%PDF-1.7\n1 0 obj << /Type /Catalog >> endobj\n%%EOF"), Err(PdfError::Parse(_))));
+    }
+}
@@ -0,0 +1,398 @@
+//! Main-content extraction modelled on Mozilla Readability.
+//!
+//! Paragraph-like elements are scored by length and comma count, their
+//! scores propagate to parent and grandparent containers, and the best
+//! container (scaled down by link density) is taken as the article body
+//! together with any siblings that look like part of the same article.
+
+use ego_tree::{NodeId, NodeRef};
+use regex::Regex;
+use scraper::{ElementRef, Html, Node, Selector};
+use std::collections::HashMap;
+
+/// Article bodies shorter than this are treated as a failed extraction.
+const MIN_ARTICLE_CHARS: usize = 250;
+/// Paragraphs shorter than this carry no signal.
+const MIN_PARAGRAPH_CHARS: usize = 25;
+
+/// Elements never part of an article body.
+const STRIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "iframe", "svg", "canvas", "button",
+    "input", "select", "textarea", "nav", "footer", "aside", "form", "dialog",
+];
+
+lazy_static::lazy_static! {
+    static ref UNLIKELY_CANDIDATES: Regex = Regex::new(
+        r"(?i)-ad-|ai2html|banner|breadcrumbs|combx|comment|community|cover-wrap|disqus|extra|footer|gdpr|header|legends|menu|related|remark|replies|rss|shoutbox|sidebar|skyscraper|social|sponsor|supplemental|ad-break|agegate|pagination|pager|popup|yom-remote|cookie|consent|newsletter|subscribe|share"
+    ).unwrap();
+    static ref MAYBE_CANDIDATE: Regex = Regex::new(r"(?i)and|article|body|column|content|main|shadow").unwrap();
+    static ref POSITIVE: Regex = Regex::new(
+        r"(?i)article|body|content|entry|hentry|h-entry|main|page|pagination|post|text|blog|story"
+    ).unwrap();
+    static ref NEGATIVE: Regex = Regex::new(
+        r"(?i)-ad-|hidden|^hid$| hid$| hid |^hid |banner|combx|comment|com-|contact|foot|footer|footnote|gdpr|masthead|media|meta|outbrain|promo|related|scroll|share|shoutbox|sidebar|skyscraper|sponsor|shopping|tags|tool|widget|cookie|consent"
+    ).unwrap();
+}
+
+/// The extracted main content of a page.
+#[derive(Debug, Clone, Default)]
+pub struct Article {
+    pub title: Option<String>,
+    pub byline: Option<String>,
+    pub published: Option<String>,
+    /// Cleaned HTML of the article body, ready for text conversion.
+    pub content_html: String,
+}
+
+/// Extract the article from `html`. Returns `None` when no container holds
+/// enough text to be an article (index pages, search results, apps), in
+/// which case callers should fall back to the full page.
+pub fn extract(html: &str) -> Option<Article> {
+    let document = Html::parse_document(html);
+
+    let body_html = best_candidate(&document)?;
+
+    Some(Article {
+        title: extract_title(&document),
+        byline: extract_byline(&document),
+        published: extract_published(&document),
+        content_html: body_html,
+    })
+}
+
+fn selector(css: &str) -> Selector {
+    Selector::parse(css).expect("static selector")
+}
+
+fn text_of(element: ElementRef) -> String {
+    element.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn first_meta(document: &Html, selectors: &[&str]) -> Option<String> {
+    selectors.iter().find_map(|css| {
+        document.select(&selector(css)).find_map(|el| {
+            el.value()
+                .attr("content")
+                .or_else(|| el.value().attr("datetime"))
+                .map(|v| v.trim().to_string())
+                .or_else(|| Some(text_of(el)))
+                .filter(|v| !v.is_empty())
+        })
+    })
+}
+
+fn extract_title(document: &Html) -> Option<String> {
+    first_meta(document, &["meta[property='og:title']", "meta[name='twitter:title']"])
+        .or_else(|| {
+            document.select(&selector("title")).next()
+                .map(text_of)
+                .filter(|t| !t.is_empty())
+        })
+        .or_else(|| document.select(&selector("h1")).next().map(text_of))
+}
+
+fn extract_byline(document: &Html) -> Option<String> {
+    first_meta(document, &[
+        "meta[name='author']",
+        "meta[property='article:author']",
+        "[itemprop='author'] [itemprop='name']",
+        "[itemprop='author']",
+        "[rel='author']",
+        ".byline",
+        ".author",
+    ])
+    .filter(|b| b.len() < 120)
+}
+
+fn extract_published(document: &Html) -> Option<String> {
+    first_meta(document, &[
+        "meta[property='article:published_time']",
+        "meta[itemprop='datePublished']",
+        "meta[name='date']",
+        "meta[name='publish-date']",
+        "meta[name='DC.date.issued']",
+        "[itemprop='datePublished']",
+        "time[datetime]",
+    ])
+}
+
+fn class_and_id(element: &scraper::node::Element) -> String {
+    format!("{} {}", element.attr("class").unwrap_or(""), element.id().unwrap_or(""))
+}
+
+/// Boilerplate containers (menus, sidebars, cookie banners, comment threads).
+fn is_unlikely(element: &scraper::node::Element) -> bool {
+    if matches!(element.name(), "body" | "article" | "main" | "html") {
+        return false;
+    }
+    let match_string = class_and_id(element);
+    let role = element.attr("role").unwrap_or("");
+    matches!(role, "navigation" | "banner" | "complementary" | "contentinfo" | "menu" | "dialog")
+        || (UNLIKELY_CANDIDATES.is_match(&match_string) && !MAYBE_CANDIDATE.is_match(&match_string))
+        || element.attr("aria-hidden") == Some("true")
+        || element.attr("hidden").is_some()
+}
+
+fn class_weight(element: &scraper::node::Element) -> f64 {
+    let mut weight = 0.0;
+    for value in [element.attr("class"), element.id()].into_iter().flatten() {
+        if NEGATIVE.is_match(value) {
+            weight -= 25.0;
+        }
+        if POSITIVE.is_match(value) {
+            weight += 25.0;
+        }
+    }
+    weight
+}
+
+fn initial_score(element: &scraper::node::Element) -> f64 {
+    let tag_score = match element.name() {
+        "article" | "main" => 10.0,
+        "div" | "section" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    tag_score + class_weight(element)
+}
+
+fn has_unlikely_ancestor(element: ElementRef) -> bool {
+    element.ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|ancestor| is_unlikely(ancestor.value()) || STRIPPED_TAGS.contains(&ancestor.value().name()))
+}
+
+/// Share of an element's text that sits inside links.
+fn link_density(element: ElementRef) -> f64 {
+    let total = text_of(element).len();
+    if total == 0 {
+        return 0.0;
+    }
+    let linked: usize = element.select(&selector("a")).map(|a| text_of(a).len()).sum();
+    linked as f64 / total as f64
+}
+
+/// Score candidates and serialize the winning container plus related
+/// siblings as HTML.
+fn best_candidate(document: &Html) -> Option<String> {
+    let mut scores: HashMap<NodeId, f64> = HashMap::new();
+
+    for paragraph in document.select(&selector("p, pre, td, blockquote")) {
+        if has_unlikely_ancestor(paragraph) {
+            continue;
+        }
+        let text = text_of(paragraph);
+        if text.len() < MIN_PARAGRAPH_CHARS {
+            continue;
+        }
+
+        let content_score = 1.0 + text.matches([',', '，']).count() as f64 + (text.len() as f64 / 100.0).min(3.0);
+
+        let ancestors = paragraph.ancestors().filter_map(ElementRef::wrap).take(3);
+        for (level, ancestor) in ancestors.enumerate() {
+            let divider = match level {
+                0 => 1.0,
+                1 => 2.0,
+                _ => 6.0,
+            };
+            let entry = scores.entry(ancestor.id()).or_insert_with(|| initial_score(ancestor.value()));
+            *entry += content_score / divider;
+        }
+    }
+
+    let (top_id, top_score) = scores.iter()
+        .filter_map(|(id, score)| {
+            let element = ElementRef::wrap(document.tree.get(*id)?)?;
+            Some((*id, score * (1.0 - link_density(element))))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    let top = ElementRef::wrap(document.tree.get(top_id)?)?;
+
+    // Pull in siblings that score well or read like article paragraphs
+    let threshold = (top_score * 0.2).max(10.0);
+    let top_classes = top.value().attr("class").unwrap_or("");
+    let mut out = String::new();
+
+    match top.parent() {
+        Some(parent) => {
+            for sibling in parent.children() {
+                let Some(element) = ElementRef::wrap(sibling) else { continue };
+                let include = if element.id() == top_id {
+                    true
+                } else if STRIPPED_TAGS.contains(&element.value().name()) || is_unlikely(element.value()) {
+                    false
+                } else {
+                    let mut bonus = 0.0;
+                    if !top_classes.is_empty() && element.value().attr("class") == Some(top_classes) {
+                        bonus += top_score * 0.2;
+                    }
+                    let score = scores.get(&element.id()).copied().unwrap_or(0.0) * (1.0 - link_density(element));
+                    if score + bonus >= threshold {
+                        true
+                    } else if element.value().name() == "p" {
+                        let text = text_of(element);
+                        let density = link_density(element);
+                        (text.len() > 80 && density < 0.25)
+                            || (!text.is_empty() && text.len() <= 80 && density == 0.0 && text.ends_with('.'))
+                    } else {
+                        false
+                    }
+                };
+                if include {
+                    serialize(sibling, &mut out);
+                }
+            }
+        }
+        None => serialize(*top, &mut out),
+    }
+
+    let text_len = Html::parse_fragment(&out).root_element().text().map(str::len).sum::<usize>();
+    (text_len >= MIN_ARTICLE_CHARS).then_some(out)
+}
+
+/// Attributes worth keeping for text conversion.
+const KEPT_ATTRIBUTES: &[&str] = &["href", "src", "alt", "title", "colspan", "rowspan", "class", "lang", "start"];
+
+fn escape_text(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// Escape a double-quoted attribute value, so `&copy=1` in a link stays
+/// literal text instead of turning into an entity when parsed again.
+fn escape_attribute(value: &str, out: &mut String) {
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// Write `node` back out as HTML, dropping scripts, navigation and
+/// boilerplate containers nested inside the article.
+fn serialize(node: NodeRef<Node>, out: &mut String) {
+    match node.value() {
+        Node::Text(text) => escape_text(text, out),
+        Node::Element(element) => {
+            let name = element.name();
+            if STRIPPED_TAGS.contains(&name) || is_unlikely(element) {
+                return;
+            }
+
+            out.push('<');
+            out.push_str(name);
+            for (attr, value) in element.attrs() {
+                if KEPT_ATTRIBUTES.contains(&attr) {
+                    out.push_str(&format!(" {}=\"", attr));
+                    escape_attribute(value, out);
+                    out.push('"');
+                }
+            }
+            out.push('>');
+
+            if matches!(name, "br" | "hr" | "img" | "wbr" | "col" | "source") {
+                return;
+            }
+            for child in node.children() {
+                serialize(child, out);
+            }
+            out.push_str(&format!("</{}>", name));
+        }
+        Node::Document | Node::Fragment => {
+            for child in node.children() {
+                serialize(child, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE_TEXT: &str = "The committee met on Tuesday, and after a long debate, it agreed to fund the new bridge.";
+
+    fn page(head: &str, article: &str) -> String {
+        format!(
+            "<html><head>{}</head><body>\
+             <nav><a href=\"/\">Home</a> <a href=\"/news\">News</a></nav>\
+             <div class=\"sidebar\"><p>Sidebar: read our most popular stories from this week, updated daily.</p></div>\
+             <div class=\"content\">{}</div>\
+             <footer><p>Copyright notice, terms of service and contact details for the newsroom.</p></footer>\
+             </body></html>",
+            head, article
+        )
+    }
+
+    fn paragraphs(count: usize) -> String {
+        (0..count).map(|i| format!("<p>{} Paragraph {}.</p>", ARTICLE_TEXT, i)).collect()
+    }
+
+    #[test]
+    fn picks_the_article_over_boilerplate() {
+        let article = extract(&page("", &paragraphs(5))).expect("an article");
+
+        assert!(article.content_html.contains("Paragraph 0.") && article.content_html.contains("Paragraph 4."));
+        assert!(!article.content_html.contains("Sidebar"));
+        assert!(!article.content_html.contains("Copyright"));
+        assert!(!article.content_html.contains("Home"));
+    }
+
+    #[test]
+    fn too_little_text_falls_back_to_the_full_page() {
+        assert!(extract(&page("", &paragraphs(1))).is_none());
+        assert!(extract("<html><body><ul><li><a href=\"/a\">A</a></li></ul></body></html>").is_none());
+    }
+
+    #[test]
+    fn title_byline_and_date_come_from_metadata() {
+        let head = "<title>Site | Bridge</title>\
+                    <meta property=\"og:title\" content=\"Bridge funded\">\
+                    <meta name=\"author\" content=\"Jane Doe\">\
+                    <meta property=\"article:published_time\" content=\"2024-05-06T07:08:09Z\">";
+        let article = extract(&page(head, &paragraphs(5))).unwrap();
+
+        assert_eq!(article.title.as_deref(), Some("Bridge funded"));
+        assert_eq!(article.byline.as_deref(), Some("Jane Doe"));
+        assert_eq!(article.published.as_deref(), Some("2024-05-06T07:08:09Z"));
+    }
+
+    #[test]
+    fn title_byline_and_date_fall_back_to_markup() {
+        let body = format!(
+            "<p class=\"byline\">By John Roe</p><time datetime=\"2024-01-02\">2 January</time>{}",
+            paragraphs(5)
+        );
+        let article = extract(&page("<title>Bridge funded</title>", &body)).unwrap();
+
+        assert_eq!(article.title.as_deref(), Some("Bridge funded"));
+        assert_eq!(article.byline.as_deref(), Some("By John Roe"));
+        assert_eq!(article.published.as_deref(), Some("2024-01-02"));
+    }
+
+    #[test]
+    fn attribute_values_are_escaped() {
+        let fragment = Html::parse_fragment("<a href=\"/q?a=1&amp;copy=2\" title=\"say &quot;hi&quot;\">link</a>");
+        let mut out = String::new();
+        serialize(fragment.tree.root(), &mut out);
+
+        assert!(out.contains("href=\"/q?a=1&amp;copy=2\""), "{}", out);
+        assert!(out.contains("title=\"say &quot;hi&quot;\""), "{}", out);
+        let reparsed = Html::parse_fragment(&out);
+        let link = reparsed.select(&selector("a")).next().unwrap();
+        assert_eq!(link.value().attr("href"), Some("/q?a=1&copy=2"));
+    }
+}
+
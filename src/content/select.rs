@@ -0,0 +1,119 @@
+//! CSS-selector queries against a page's HTML.
+
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Match {
+    pub tag: String,
+    /// Visible text with whitespace collapsed, cut at `max_chars`.
+    pub text: String,
+    pub text_truncated: bool,
+    /// Attributes, sorted by name.
+    pub attributes: Map<String, Value>,
+    /// Outer HTML, cut at `max_chars`.
+    pub html: String,
+    pub html_truncated: bool,
+}
+
+/// Whitespace-collapsed text of `element`, stopping once it is longer than
+/// `max_chars` so `body` or a page-wide `div` is never gathered in full.
+fn element_text(element: ElementRef, max_chars: usize) -> (String, bool) {
+    let mut text = String::new();
+    let mut chars = 0;
+    for word in element.text().flat_map(str::split_whitespace) {
+        let separator = (!text.is_empty()).then_some(' ');
+        for c in separator.into_iter().chain(word.chars()) {
+            if chars == max_chars {
+                return (text.trim_end().to_string(), true);
+            }
+            text.push(c);
+            chars += 1;
+        }
+    }
+    (text, false)
+}
+
+/// Elements of `html` matching `css`, in document order. Returns the first
+/// `max_results` matches and the total match count. Each element's text and
+/// outer HTML are cut at `max_chars`.
+pub fn select(html: &str, css: &str, max_results: usize, max_chars: usize) -> Result<(Vec<Match>, usize), String> {
+    let selector = Selector::parse(css)
+        .map_err(|e| format!("❌ Invalid CSS selector '{}': {}", css, e))?;
+    let document = Html::parse_document(html);
+
+    let mut total = 0;
+    let mut matches = Vec::new();
+    for element in document.select(&selector) {
+        total += 1;
+        if matches.len() >= max_results {
+            continue;
+        }
+
+        let outer = element.html();
+        let html_truncated = outer.chars().count() > max_chars;
+        let html = if html_truncated {
+            outer.chars().take(max_chars).collect()
+        } else {
+            outer
+        };
+        let (text, text_truncated) = element_text(element, max_chars);
+
+        matches.push(Match {
+            tag: element.value().name().to_string(),
+            text,
+            text_truncated,
+            attributes: element.value().attrs()
+                .map(|(name, value)| (name.to_string(), Value::String(value.to_string())))
+                .collect(),
+            html,
+            html_truncated,
+        });
+    }
+
+    Ok((matches, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "<html><body><div id=\"main\"><a href=\"/a\" class=\"x\">First   link</a>\
+                        <p>Some <b>bold</b> text</p><a href=\"/b\">Second</a></div></body></html>";
+
+    #[test]
+    fn returns_matches_in_document_order_with_total() {
+        let (matches, total) = select(PAGE, "a", 1, 1000).unwrap();
+
+        assert_eq!(total, 2);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].tag, "a");
+        assert_eq!(matches[0].text, "First link");
+        assert_eq!(matches[0].attributes.keys().collect::<Vec<_>>(), ["class", "href"]);
+        assert!(!matches[0].html_truncated && !matches[0].text_truncated);
+    }
+
+    #[test]
+    fn text_and_html_are_cut_at_the_limit() {
+        let (matches, _) = select(PAGE, "#main", 5, 12).unwrap();
+
+        assert_eq!(matches[0].text, "First link S");
+        assert!(matches[0].text_truncated);
+        assert_eq!(matches[0].html, "<div id=\"mai");
+        assert!(matches[0].html_truncated);
+    }
+
+    #[test]
+    fn text_exactly_at_the_limit_is_whole() {
+        let (matches, _) = select(PAGE, "p", 5, "Some bold text".len()).unwrap();
+
+        assert_eq!(matches[0].text, "Some bold text");
+        assert!(!matches[0].text_truncated);
+    }
+
+    #[test]
+    fn invalid_selectors_are_errors() {
+        assert!(select(PAGE, "a[", 5, 100).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+//! Structured extraction of HTML tables. Spanning cells are expanded so
+//! every row has one value per column, header rows are detected, and each
+//! table can be written out as CSV or Markdown.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::Serialize;
+
+/// Upper bound for `colspan`/`rowspan`, against malformed or hostile markup.
+const MAX_SPAN: usize = 1000;
+/// Largest grid (rows × columns) laid out for one table. Rows past it are
+/// dropped and the table is marked truncated.
+pub const MAX_CELLS: usize = 100_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Table {
+    /// Position among all `<table>` elements of the page, in document order.
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    /// Column headers; empty when the table has no header row.
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// Rows were dropped because the table exceeded `MAX_CELLS`.
+    pub truncated: bool,
+}
+
+impl Table {
+    pub fn columns(&self) -> usize {
+        self.headers.len().max(self.rows.iter().map(Vec::len).max().unwrap_or(0))
+    }
+
+    /// Column headers, or `Column 1`, `Column 2`, ... for headerless tables.
+    fn header_or_placeholder(&self) -> Vec<String> {
+        if self.headers.is_empty() {
+            (1..=self.columns()).map(|i| format!("Column {}", i)).collect()
+        } else {
+            self.headers.clone()
+        }
+    }
+
+    pub fn to_csv(&self, max_rows: usize) -> String {
+        let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(Vec::new());
+        if !self.headers.is_empty() {
+            let _ = writer.write_record(&self.headers);
+        }
+        for row in self.rows.iter().take(max_rows) {
+            let _ = writer.write_record(row);
+        }
+        let bytes = writer.into_inner().unwrap_or_default();
+        String::from_utf8_lossy(&bytes).trim_end().to_string()
+    }
+
+    pub fn to_markdown(&self, max_rows: usize) -> String {
+        let columns = self.columns();
+        let escape = |cell: &str| cell.replace('|', "\\|");
+        let format_row = |row: &[String]| {
+            let cells: Vec<String> = (0..columns)
+                .map(|i| escape(row.get(i).map(String::as_str).unwrap_or("")))
+                .collect();
+            format!("| {} |", cells.join(" | "))
+        };
+
+        let mut lines = vec![format_row(&self.header_or_placeholder())];
+        lines.push(format!("|{}", " --- |".repeat(columns)));
+        lines.extend(self.rows.iter().take(max_rows).map(|row| format_row(row)));
+        lines.join("\n")
+    }
+}
+
+fn selector(css: &str) -> Selector {
+    Selector::parse(css).expect("static selector")
+}
+
+/// Rows of `table` in document order, skipping rows of nested tables.
+pub fn table_rows(table: ElementRef) -> Vec<ElementRef> {
+    let mut rows = Vec::new();
+    for child in table.children().filter_map(ElementRef::wrap) {
+        match child.value().name() {
+            "tr" => rows.push(child),
+            "thead" | "tbody" | "tfoot" => rows.extend(
+                child.children().filter_map(ElementRef::wrap).filter(|r| r.value().name() == "tr"),
+            ),
+            _ => {}
+        }
+    }
+    rows
+}
+
+/// Visible text of a cell, without scripts, styles or Wikipedia-style
+/// citation markers (`<sup class="reference">`).
+fn cell_text(cell: ElementRef) -> String {
+    fn collect(node: NodeRef<Node>, out: &mut String) {
+        for child in node.children() {
+            match child.value() {
+                Node::Text(text) => out.push_str(text),
+                Node::Element(element) => {
+                    let is_citation = element.name() == "sup" && element.classes().any(|c| c == "reference");
+                    if is_citation || matches!(element.name(), "script" | "style" | "template") {
+                        continue;
+                    }
+                    if element.name() == "br" {
+                        out.push(' ');
+                    }
+                    collect(child, out);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut text = String::new();
+    collect(*cell, &mut text);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn span(cell: ElementRef, attribute: &str) -> usize {
+    cell.value().attr(attribute)
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|&v| v > 0)
+        .unwrap_or(1)
+        .min(MAX_SPAN)
+}
+
+/// A table laid out on a grid, the number of leading header rows, and
+/// whether rows were dropped to stay within `MAX_CELLS`.
+type Grid = (Vec<Vec<String>>, usize, bool);
+
+/// Lay the rows of `table` out on a grid, copying spanning cells into
+/// every position they cover. Also reports how many leading rows are
+/// header rows.
+fn grid(table: ElementRef) -> Grid {
+    let rows = table_rows(table);
+    let mut grid: Vec<Vec<Option<String>>> = vec![Vec::new(); rows.len()];
+    let mut header_rows = 0;
+    let mut in_header = true;
+    let mut kept = rows.len();
+    let mut allocated = 0;
+
+    'rows: for (r, row) in rows.iter().enumerate() {
+        let cells: Vec<ElementRef> = row.children()
+            .filter_map(ElementRef::wrap)
+            .filter(|c| matches!(c.value().name(), "td" | "th"))
+            .collect();
+
+        let in_thead = row.parent()
+            .and_then(ElementRef::wrap)
+            .is_some_and(|p| p.value().name() == "thead");
+        let all_th = !cells.is_empty() && cells.iter().all(|c| c.value().name() == "th");
+        if in_header && (in_thead || all_th) {
+            header_rows = r + 1;
+        } else {
+            in_header = false;
+        }
+
+        let mut c = 0;
+        for cell in cells {
+            while grid[r].get(c).is_some_and(Option::is_some) {
+                c += 1;
+            }
+            // Stop before a cell that cannot fit even on its own row; keep
+            // this row if part of it was laid out
+            let width = c + span(cell, "colspan");
+            if allocated + width > MAX_CELLS {
+                kept = if grid[r].is_empty() { r } else { r + 1 };
+                break 'rows;
+            }
+            // Rowspans end at the last row rather than adding phantom rows,
+            // and shrink to what the cell budget allows
+            let rowspan = span(cell, "rowspan")
+                .min(rows.len() - r)
+                .min((MAX_CELLS - allocated) / width);
+
+            let text = cell_text(cell);
+            for target in &mut grid[r..r + rowspan] {
+                if target.len() < width {
+                    allocated += width - target.len();
+                    target.resize(width, None);
+                }
+                for slot in &mut target[c..width] {
+                    *slot = Some(text.clone());
+                }
+            }
+            c = width;
+        }
+    }
+
+    // Rows below a cut-off may hold parts of rowspans; drop them too, and
+    // any rows that padding to the full width would push over the limit
+    grid.truncate(kept);
+    let columns = grid.iter().map(Vec::len).max().unwrap_or(0);
+    grid.truncate(MAX_CELLS / columns.max(1));
+    let truncated = grid.len() < rows.len();
+
+    let grid: Vec<Vec<String>> = grid.into_iter()
+        .map(|row| {
+            let mut row: Vec<String> = row.into_iter().map(Option::unwrap_or_default).collect();
+            row.resize(columns, String::new());
+            row
+        })
+        .collect();
+    let header_rows = header_rows.min(grid.len());
+    (grid, header_rows, truncated)
+}
+
+/// Merge several header rows into one, joining the distinct labels of each
+/// column (`Population / 2020`).
+fn merge_headers(rows: &[Vec<String>]) -> Vec<String> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    (0..columns)
+        .map(|i| {
+            let mut parts: Vec<&str> = Vec::new();
+            for row in rows {
+                if let Some(label) = row.get(i).map(String::as_str).filter(|l| !l.is_empty()) {
+                    if parts.last() != Some(&label) {
+                        parts.push(label);
+                    }
+                }
+            }
+            parts.join(" / ")
+        })
+        .collect()
+}
+
+/// Every `<table>` in `html`, in document order. Tables without any cells
+/// keep their index but are left out.
+pub fn extract(html: &str) -> Vec<Table> {
+    let document = Html::parse_document(html);
+
+    document.select(&selector("table"))
+        .enumerate()
+        .filter_map(|(index, table)| {
+            let caption = table.children()
+                .filter_map(ElementRef::wrap)
+                .find(|c| c.value().name() == "caption")
+                .map(cell_text)
+                .filter(|c| !c.is_empty());
+
+            let (mut rows, header_rows, truncated) = grid(table);
+            if rows.iter().all(|row| row.iter().all(String::is_empty)) {
+                return None;
+            }
+
+            // A table that is nothing but header cells is data, not headers
+            let header_rows = if header_rows == rows.len() { 0 } else { header_rows };
+            let headers = merge_headers(&rows[..header_rows]);
+            rows.drain(..header_rows);
+
+            Some(Table { index, caption, headers, rows, truncated })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn only_table(html: &str) -> Table {
+        let mut tables = extract(html);
+        assert_eq!(tables.len(), 1);
+        tables.remove(0)
+    }
+
+    #[test]
+    fn spans_are_copied_into_every_position() {
+        let table = only_table(
+            "<table><tr><th>Name</th><th colspan=2>Score</th></tr>\
+             <tr><td rowspan=2>Ann</td><td>1</td><td>2</td></tr>\
+             <tr><td>3</td><td>4</td></tr></table>",
+        );
+
+        assert_eq!(table.headers, ["Name", "Score", "Score"]);
+        assert_eq!(table.rows, [["Ann", "1", "2"], ["Ann", "3", "4"]]);
+        assert!(!table.truncated);
+    }
+
+    #[test]
+    fn multi_level_headers_are_merged() {
+        let table = only_table(
+            "<table><thead><tr><th rowspan=2>City</th><th colspan=2>Population</th></tr>\
+             <tr><th>2010</th><th>2020</th></tr></thead>\
+             <tbody><tr><td>Oslo</td><td>580</td><td>700<sup class=\"reference\">[1]</sup></td></tr></tbody></table>",
+        );
+
+        assert_eq!(table.headers, ["City", "Population / 2010", "Population / 2020"]);
+        assert_eq!(table.rows, [["Oslo", "580", "700"]]);
+    }
+
+    #[test]
+    fn rowspans_stop_at_the_last_row() {
+        let table = only_table("<table><tr><td rowspan=1000>a</td><td>b</td></tr><tr><td>c</td></tr></table>");
+
+        assert_eq!(table.rows, [["a", "b"], ["a", "c"]]);
+    }
+
+    #[test]
+    fn short_rows_are_padded_and_empty_tables_skipped() {
+        let tables = extract("<table></table><table><tr><td>a</td><td>b</td></tr><tr><td>c</td></tr></table>");
+
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].index, 1);
+        assert_eq!(tables[0].rows, [["a", "b"], ["c", ""]]);
+    }
+
+    #[test]
+    fn hostile_spans_are_cut_off_at_the_cell_limit() {
+        let row = "<tr><td rowspan=1000 colspan=1000>x</td><td rowspan=1000 colspan=1000>y</td></tr>";
+        let table = only_table(&format!("<table>{}</table>", row.repeat(200)));
+
+        assert!(table.truncated);
+        assert!(table.rows.len() * table.columns() <= MAX_CELLS);
+        assert!(!table.rows.is_empty());
+    }
+}
@@ -0,0 +1,401 @@
+//! Pattern matching for the `find` tool. Literal and regex patterns are
+//! compiled to one `Regex`, with limits on pattern length, compiled size
+//! and search time so a pathological pattern cannot stall a request.
+//! Fuzzy mode instead ranks passages against the query with BM25.
+
+use crate::rank::{self, Bm25};
+use regex::{Regex, RegexBuilder};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Longest pattern accepted, in characters.
+const MAX_PATTERN_CHARS: usize = 1000;
+/// Cap on the compiled program and on the lazy DFA cache.
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+const REGEX_NEST_LIMIT: u32 = 50;
+/// Wall-clock budget for one search.
+pub const SEARCH_TIME_LIMIT: Duration = Duration::from_secs(5);
+/// Lines per passage scored in fuzzy mode.
+const PASSAGE_LINES: usize = 3;
+
+#[derive(Debug, Error)]
+pub enum FindError {
+    #[error("❌ Pattern is too long ({len} characters; the limit is {limit}).")]
+    PatternTooLong { len: usize, limit: usize },
+    #[error("❌ Invalid regex '{pattern}': {message}")]
+    InvalidRegex { pattern: String, message: String },
+    #[error("❌ Search timed out after {}s. Try a simpler or more specific pattern.", .0.as_secs())]
+    Timeout(Duration),
+    #[error("❌ The query has no searchable words (only punctuation or very common words).")]
+    NoSearchTerms,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FindOptions {
+    /// Treat the pattern as a regular expression instead of literal text.
+    pub regex: bool,
+    pub case_sensitive: bool,
+    /// Only match at word boundaries.
+    pub whole_word: bool,
+}
+
+pub fn compile(pattern: &str, options: &FindOptions) -> Result<Regex, FindError> {
+    let len = pattern.chars().count();
+    if len > MAX_PATTERN_CHARS {
+        return Err(FindError::PatternTooLong { len, limit: MAX_PATTERN_CHARS });
+    }
+
+    let body = if options.regex { pattern.to_string() } else { regex::escape(pattern) };
+    let body = if options.whole_word { format!(r"\b(?:{})\b", body) } else { body };
+
+    RegexBuilder::new(&body)
+        .case_insensitive(!options.case_sensitive)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .dfa_size_limit(REGEX_SIZE_LIMIT)
+        .nest_limit(REGEX_NEST_LIMIT)
+        .build()
+        .map_err(|e| {
+            let message = match e {
+                regex::Error::CompiledTooBig(limit) => format!("pattern is too complex (compiled size exceeds {} bytes)", limit),
+                other => other.to_string(),
+            };
+            FindError::InvalidRegex { pattern: pattern.to_string(), message }
+        })
+}
+
+/// A match as the range of page lines it covers, both ends inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// `L12` or `L12-L14`.
+    pub fn label(&self) -> String {
+        if self.start == self.end {
+            format!("L{}", self.start)
+        } else {
+            format!("L{}-L{}", self.start, self.end)
+        }
+    }
+}
+
+/// Page text with every whitespace run, line breaks included, collapsed to
+/// one space, so phrases split by hard wrapping can still be matched.
+pub struct NormalizedText {
+    pub text: String,
+    /// `(offset in text, line number)` for the first word of each
+    /// non-blank line, in increasing order.
+    line_starts: Vec<(usize, usize)>,
+}
+
+impl NormalizedText {
+    pub fn new(content: &str) -> Self {
+        let mut text = String::with_capacity(content.len());
+        let mut line_starts = Vec::new();
+
+        for (line_num, line) in content.lines().enumerate() {
+            let mut words = line.split_whitespace().peekable();
+            if words.peek().is_none() {
+                continue;
+            }
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            line_starts.push((text.len(), line_num));
+            for (i, word) in words.enumerate() {
+                if i > 0 {
+                    text.push(' ');
+                }
+                text.push_str(word);
+            }
+        }
+
+        Self { text, line_starts }
+    }
+
+    /// Line holding the character at `offset` in the normalized text.
+    pub fn line_at(&self, offset: usize) -> usize {
+        let i = self.line_starts.partition_point(|(start, _)| *start <= offset);
+        self.line_starts.get(i.saturating_sub(1)).map_or(0, |(_, line)| *line)
+    }
+
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        Span { start: self.line_at(start), end: self.line_at(end.saturating_sub(1).max(start)) }
+    }
+}
+
+/// Whether a regex uses `^`, `$` or the `m` flag outside character
+/// classes. Such patterns are about lines, which whitespace normalization
+/// would erase.
+fn is_line_anchored(pattern: &str) -> bool {
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '^' | '$' if !in_class => return true,
+            '(' if !in_class && chars.peek() == Some(&'?') => {
+                chars.next();
+                let flags: String = chars.clone().take_while(|c| c.is_ascii_alphabetic() || *c == '-').collect();
+                if flags.split('-').next().is_some_and(|enabled| enabled.contains('m')) {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Line spans of every match of `regex` in `content`, in document order.
+/// Matching runs on the whitespace-normalized text so phrases can span
+/// lines, except for line-anchored patterns, which are matched against
+/// each line as written. Several matches on the same lines are reported
+/// once.
+pub fn find_spans(content: &str, regex: &Regex) -> Result<Vec<Span>, FindError> {
+    let deadline = Instant::now() + SEARCH_TIME_LIMIT;
+    let mut spans: Vec<Span> = Vec::new();
+    let mut push = |span: Span| {
+        if spans.last() != Some(&span) {
+            spans.push(span);
+        }
+    };
+
+    if is_line_anchored(regex.as_str()) {
+        for (line_num, line) in content.lines().enumerate() {
+            if Instant::now() > deadline {
+                return Err(FindError::Timeout(SEARCH_TIME_LIMIT));
+            }
+            if regex.find_iter(line).any(|m| !m.as_str().trim().is_empty()) {
+                push(Span { start: line_num, end: line_num });
+            }
+        }
+        return Ok(spans);
+    }
+
+    let normalized = NormalizedText::new(content);
+    for m in regex.find_iter(&normalized.text) {
+        if Instant::now() > deadline {
+            return Err(FindError::Timeout(SEARCH_TIME_LIMIT));
+        }
+        if m.as_str().trim().is_empty() {
+            continue;
+        }
+        push(normalized.span(m.start(), m.end()));
+    }
+    Ok(spans)
+}
+
+/// A passage found by fuzzy search.
+#[derive(Debug, Clone)]
+pub struct Passage {
+    /// Lines of the passage that contain query terms.
+    pub span: Span,
+    pub score: f64,
+    /// Query terms (stemmed) found in the passage.
+    pub terms: Vec<String>,
+}
+
+/// Passages of `content` relevant to `query`, best first. Windows of
+/// `PASSAGE_LINES` lines are scored with BM25 and trimmed to the lines
+/// holding query terms; a window overlapping a better one is dropped.
+pub fn ranked_passages(content: &str, query: &str) -> Result<Vec<Passage>, FindError> {
+    let terms = rank::tokenize(query);
+    if terms.is_empty() {
+        return Err(FindError::NoSearchTerms);
+    }
+
+    let line_tokens: Vec<Vec<String>> = content.lines().map(rank::tokenize).collect();
+    let starts: Vec<usize> = (0..line_tokens.len()).filter(|&i| !line_tokens[i].is_empty()).collect();
+    let window = |start: usize| start..(start + PASSAGE_LINES).min(line_tokens.len());
+    let index = Bm25::new(starts.iter().map(|&start| line_tokens[window(start)].concat()));
+
+    let mut covered = vec![false; line_tokens.len()];
+    let mut passages = Vec::new();
+    for (i, score) in index.rank(&terms) {
+        let matched = index.matched_terms(&terms, i);
+        let hit_lines: Vec<usize> = window(starts[i])
+            .filter(|&line| line_tokens[line].iter().any(|t| matched.contains(&t.as_str())))
+            .collect();
+        let (Some(&start), Some(&end)) = (hit_lines.first(), hit_lines.last()) else { continue };
+        if covered[start..=end].iter().any(|&c| c) {
+            continue;
+        }
+        covered[start..=end].iter_mut().for_each(|c| *c = true);
+        passages.push(Passage {
+            span: Span { start, end },
+            score,
+            terms: matched.into_iter().map(str::to_string).collect(),
+        });
+    }
+    Ok(passages)
+}
+
+/// A run of page lines shown as one block, holding the context of one or
+/// more matches whose windows overlap or touch.
+#[derive(Debug, Clone)]
+pub struct ContextWindow {
+    /// First and last line shown, inclusive.
+    pub start: usize,
+    pub end: usize,
+    /// Indices into the spans passed to `context_windows`, ascending.
+    pub members: Vec<usize>,
+}
+
+impl ContextWindow {
+    fn touches(&self, start: usize, end: usize) -> bool {
+        start <= self.end + 1 && self.start <= end + 1
+    }
+}
+
+/// Group `spans` into context blocks of `context` lines either side,
+/// merging blocks that overlap or are adjacent so no line is shown twice.
+/// Blocks keep the order of their first member.
+pub fn context_windows(spans: &[Span], context: usize, line_count: usize) -> Vec<ContextWindow> {
+    let last_line = line_count.saturating_sub(1);
+    let mut windows: Vec<ContextWindow> = Vec::new();
+
+    for (i, span) in spans.iter().enumerate() {
+        let mut window = ContextWindow {
+            start: span.start.saturating_sub(context),
+            end: (span.end + context).min(last_line),
+            members: vec![i],
+        };
+        // Absorb every earlier block this one reaches; growing may bring
+        // further blocks into reach, so repeat until nothing changes.
+        let mut position = None;
+        while let Some(j) = windows.iter().position(|w| w.touches(window.start, window.end)) {
+            let other = windows.remove(j);
+            window.start = window.start.min(other.start);
+            window.end = window.end.max(other.end);
+            window.members.extend(other.members);
+            position = Some(position.map_or(j, |p: usize| p.min(j)));
+        }
+        window.members.sort_unstable();
+        windows.insert(position.unwrap_or(windows.len()), window);
+    }
+    windows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(regex: bool, case_sensitive: bool, whole_word: bool) -> FindOptions {
+        FindOptions { regex, case_sensitive, whole_word }
+    }
+
+    #[test]
+    fn literal_patterns_are_escaped_and_case_insensitive_by_default() {
+        let re = compile("a.b (c)", &FindOptions::default()).unwrap();
+        assert!(re.is_match("A.B (C)"));
+        assert!(!re.is_match("axb (c)"));
+    }
+
+    #[test]
+    fn case_sensitive_and_whole_word_modes() {
+        let re = compile("Rust", &options(false, true, false)).unwrap();
+        assert!(re.is_match("Rustacean") && !re.is_match("rust"));
+
+        let re = compile("cat|dog", &options(true, false, true)).unwrap();
+        assert!(re.is_match("a Dog barks"));
+        assert!(!re.is_match("category") && !re.is_match("hotdogs"));
+    }
+
+    #[test]
+    fn bad_patterns_are_rejected() {
+        assert!(matches!(compile("(unclosed", &options(true, false, false)), Err(FindError::InvalidRegex { .. })));
+        assert!(matches!(
+            compile(&"a".repeat(MAX_PATTERN_CHARS + 1), &FindOptions::default()),
+            Err(FindError::PatternTooLong { .. })
+        ));
+        let huge = compile(r"\w{1000}\w{1000}\w{1000}", &options(true, false, false));
+        assert!(matches!(huge, Err(FindError::InvalidRegex { message, .. }) if message.contains("too complex")));
+    }
+
+    #[test]
+    fn phrases_match_across_line_breaks() {
+        let content = "intro\n\nthe quick\n   brown fox\njumps";
+        let re = compile("quick brown", &FindOptions::default()).unwrap();
+        let spans = find_spans(content, &re).unwrap();
+        assert_eq!(spans, vec![Span { start: 2, end: 3 }]);
+        assert_eq!(spans[0].label(), "L2-L3");
+        assert_eq!(Span { start: 4, end: 4 }.label(), "L4");
+    }
+
+    #[test]
+    fn line_anchors_match_per_line() {
+        let content = "# Title\nsome text # not a heading\n# Second\nend.";
+        let re = compile("^# ", &options(true, false, false)).unwrap();
+        assert_eq!(find_spans(content, &re).unwrap(), vec![Span { start: 0, end: 0 }, Span { start: 2, end: 2 }]);
+
+        let re = compile(r"\.$", &options(true, false, false)).unwrap();
+        assert_eq!(find_spans(content, &re).unwrap(), vec![Span { start: 3, end: 3 }]);
+    }
+
+    #[test]
+    fn line_anchor_detection() {
+        assert!(is_line_anchored("^foo") && is_line_anchored("foo$") && is_line_anchored("(?m)foo"));
+        assert!(is_line_anchored("(?im:foo)"));
+        assert!(!is_line_anchored(r"\$5") && !is_line_anchored("[^a-z]+") && !is_line_anchored("(?i)foo"));
+        assert!(!is_line_anchored("(?i-m)foo") && !is_line_anchored("(?:a|b)"));
+    }
+
+    #[test]
+    fn fuzzy_queries_need_searchable_words() {
+        assert!(matches!(ranked_passages("some text", "the of !!"), Err(FindError::NoSearchTerms)));
+    }
+
+    #[test]
+    fn fuzzy_passages_are_ranked_trimmed_and_disjoint() {
+        let content = "Cooking pasta takes water.\n\nUnrelated line.\nParsing tables is hard.\nWe parse nested tables.\nTables everywhere.\nThe end.";
+        let passages = ranked_passages(content, "parsing tables").unwrap();
+
+        let best = &passages[0];
+        assert_eq!(best.span, Span { start: 3, end: 5 });
+        assert!(best.terms.iter().any(|t| t.starts_with("pars")) && best.terms.iter().any(|t| t.starts_with("tabl")));
+        assert!(passages.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        for (i, a) in passages.iter().enumerate() {
+            for b in &passages[i + 1..] {
+                assert!(a.span.end < b.span.start || b.span.end < a.span.start, "{:?} overlaps {:?}", a.span, b.span);
+            }
+        }
+        // Lines without a query term are trimmed from the window's ends.
+        assert!(passages.iter().all(|p| p.span.start != 2));
+    }
+
+    fn bounds(windows: &[ContextWindow]) -> Vec<(usize, usize, Vec<usize>)> {
+        windows.iter().map(|w| (w.start, w.end, w.members.clone())).collect()
+    }
+
+    #[test]
+    fn context_windows_merge_overlapping_and_adjacent_blocks() {
+        let line = |n| Span { start: n, end: n };
+        // 0..=4 and 5..=9 touch; 20..=24 stands alone; the window is clamped to the page.
+        let windows = context_windows(&[line(2), line(7), line(22), line(99)], 2, 100);
+        assert_eq!(bounds(&windows), vec![(0, 9, vec![0, 1]), (20, 24, vec![2]), (97, 99, vec![3])]);
+
+        let windows = context_windows(&[line(2), line(8)], 2, 100);
+        assert_eq!(bounds(&windows), vec![(0, 4, vec![0]), (6, 10, vec![1])]);
+    }
+
+    #[test]
+    fn context_windows_merge_transitively_and_keep_first_member_order() {
+        let line = |n| Span { start: n, end: n };
+        // The last span bridges the first two blocks into one.
+        let windows = context_windows(&[line(10), line(30), line(50), Span { start: 16, end: 24 }], 3, 100);
+        assert_eq!(bounds(&windows), vec![(7, 33, vec![0, 1, 3]), (47, 53, vec![2])]);
+
+        // A later match listed first keeps its block first.
+        let windows = context_windows(&[line(50), line(10), Span { start: 48, end: 49 }], 1, 100);
+        assert_eq!(bounds(&windows), vec![(47, 51, vec![0, 2]), (9, 11, vec![1])]);
+    }
+}
+
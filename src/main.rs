@@ -10,7 +10,14 @@ use tower::ServiceExt;
 use tower_http::cors::CorsLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod content;
+mod find;
 mod mcp_server;
+mod metrics;
+mod rank;
+mod retrieve;
+mod search;
+mod tokens;
 use mcp_server::McpServer;
 
 #[tokio::main]
@@ -103,11 +110,7 @@ fn convert_lambda_to_axum_request(lambda_request: Request) -> Result<AxumRequest
         }
     } else {
         // Already just a path, remove /prod prefix if present
-        if uri_str.starts_with("/prod") {
-            &uri_str[5..]
-        } else {
-            &uri_str
-        }
+        uri_str.strip_prefix("/prod").unwrap_or(&uri_str)
     };
     
     // Ensure we have a valid path
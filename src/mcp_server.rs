@@ -5,12 +5,25 @@ use axum::{
     routing::{get, post, delete},
     Router,
 };
+use futures::future::join_all;
+use futures::stream::{self, StreamExt};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tracing::{info, error, debug, warn};
 use std::collections::HashMap;
-use scraper::{Html, Selector};
-use html2text::from_read;
+
+use crate::content::encoding::Charset;
+use crate::content::formats::{ContentError, DocumentKind};
+use crate::content::metadata::PageMetadata;
+use crate::content::pdf::PdfError;
+use crate::content::{self, ContentMode, RenderOptions, RenderedPage, Source};
+use crate::find::{self, FindError, FindOptions, Span};
+use crate::metrics;
+use crate::rank;
+use crate::retrieve;
+use crate::tokens;
+use crate::search::{self, SearchFilters, SearchQuery, SearchResult, Vertical};
 
 /// MCP Server implementation for GPT-OSS browser tools
 pub struct McpServer;
@@ -51,6 +64,65 @@ pub struct McpContent {
 #[derive(Debug, Serialize)]
 pub struct McpToolResult {
     pub content: Vec<McpContent>,
+    #[serde(rename = "structuredContent", skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
+}
+
+/// What a tool returns: text for the model plus, for tools that have it,
+/// the same information as JSON for `structuredContent`.
+#[derive(Debug)]
+pub struct ToolOutput {
+    pub text: String,
+    pub structured: Option<Value>,
+}
+
+impl From<String> for ToolOutput {
+    fn from(text: String) -> Self {
+        Self { text, structured: None }
+    }
+}
+
+/// Rows per table `extract_tables` returns unless asked for more.
+const DEFAULT_TABLE_ROWS: usize = 50;
+const DEFAULT_SELECT_RESULTS: usize = 20;
+const DEFAULT_SELECT_HTML_CHARS: usize = 2000;
+/// Most URLs `open_many` accepts in one call, and its highest parallelism.
+const MAX_OPEN_MANY_URLS: usize = 10;
+/// Pages `open_many` fetches at once, overridable with `OPEN_CONCURRENCY`.
+const DEFAULT_OPEN_CONCURRENCY: usize = 4;
+/// Passages `retrieve` returns unless asked for more.
+const DEFAULT_RETRIEVE_PASSAGES: usize = 5;
+const MAX_RETRIEVE_PASSAGES: usize = 20;
+/// Results `research` reads, and passages it returns from each.
+const DEFAULT_RESEARCH_RESULTS: usize = 3;
+const DEFAULT_RESEARCH_PASSAGES: usize = 2;
+const MAX_RESEARCH_PASSAGES: usize = 5;
+/// Lines shown before and after each `find` match.
+const DEFAULT_FIND_CONTEXT_LINES: usize = 2;
+const MAX_FIND_CONTEXT_LINES: usize = 20;
+/// Matches listed in one `find` response unless asked for more.
+const DEFAULT_FIND_MATCHES: usize = 10;
+const MAX_FIND_MATCHES: usize = 100;
+/// Matches listed per page when `find` searches the whole session.
+const DEFAULT_SESSION_FIND_MATCHES: usize = 3;
+
+fn default_open_concurrency() -> usize {
+    std::env::var("OPEN_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .map_or(DEFAULT_OPEN_CONCURRENCY, |n| n.clamp(1, MAX_OPEN_MANY_URLS))
+}
+
+/// A `find` hit: the lines it covers and an optional note for its heading.
+type FindHit = (Span, Option<String>);
+
+/// One page's outcome in a session-wide `find`.
+struct SessionFind {
+    cursor: usize,
+    url: String,
+    title: Option<String>,
+    text: String,
+    hits: Result<Vec<FindHit>, String>,
 }
 
 // Tool registry for MCP protocol compliance
@@ -65,7 +137,17 @@ lazy_static::lazy_static! {
                 "type": "object",
                 "properties": {
                     "query": {"type": "string", "description": "Search query"},
-                    "topn": {"type": "number", "description": "Number of results to return (default: 10)", "default": 10}
+                    "type": {"type": "string", "enum": ["web", "news", "images", "videos"], "description": "Search vertical (default: web). News and video results include publisher and date; image results include the source page, dimensions and alt text", "default": "web"},
+                    "topn": {"type": "number", "description": "Number of results to return (default: 10)", "default": 10},
+                    "page": {"type": "number", "description": "1-based page of topn results to return (default: 1)", "default": 1},
+                    "offset": {"type": "number", "description": "Zero-based index of the first result to return (overrides page)"},
+                    "cursor": {"type": "string", "description": "Continuation token from a previous search to fetch the next results (overrides offset and page; only valid with the backend that issued it)"},
+                    "sites": {"type": "array", "items": {"type": "string"}, "description": "Only return results from these domains (e.g. [\"docs.rs\"])"},
+                    "exclude_sites": {"type": "array", "items": {"type": "string"}, "description": "Exclude results from these domains"},
+                    "time_range": {"type": "string", "enum": ["day", "week", "month", "year"], "description": "Only return results published within this period"},
+                    "region": {"type": "string", "description": "Two-letter country code to localize results (e.g. \"us\", \"de\")"},
+                    "language": {"type": "string", "description": "Two-letter language code for results (e.g. \"en\", \"de\")"},
+                    "safe_search": {"type": "string", "enum": ["off", "moderate", "strict"], "description": "Safe search level (backend default if omitted)"}
                 },
                 "required": ["query"]
             }
@@ -79,7 +161,11 @@ lazy_static::lazy_static! {
                 "properties": {
                     "url": {"type": "string", "description": "URL to open"},
                     "loc": {"type": "number", "description": "Starting line number (default: 0)", "default": 0},
-                    "num_lines": {"type": "number", "description": "Number of lines to show (-1 for all)", "default": -1}
+                    "num_lines": {"type": "number", "description": "Number of lines to show (-1 for all)", "default": -1},
+                    "max_tokens": {"type": "integer", "description": "Token budget for the lines shown (o200k_harmony tokens), cut on a line boundary; 0 for no limit. Defaults to the server's limit (10000)", "minimum": 0},
+                    "mode": {"type": "string", "enum": ["article", "full"], "description": "\"article\" shows only the main content with title, byline and date; \"full\" shows the whole page", "default": "article"},
+                    "format": {"type": "string", "enum": ["text", "markdown"], "description": "\"markdown\" keeps headings, fenced code blocks, tables and nested lists", "default": "text"},
+                    "wrap": {"type": "number", "description": "Column to wrap prose at (0 for no wrapping)", "default": 80}
                 },
                 "required": ["url"]
             }
@@ -87,39 +173,253 @@ lazy_static::lazy_static! {
         
         tools.insert("find", json!({
             "name": "find",
-            "description": "Find specific text patterns in the currently opened page",
+            "description": "Find specific text patterns in the currently opened page. Whitespace and line breaks are collapsed before matching, so phrases spanning lines are found; matches are reported as Lstart-Lend line ranges",
             "inputSchema": {
                 "type": "object",
                 "properties": {
                     "pattern": {"type": "string", "description": "Text pattern to search for"},
-                    "url": {"type": "string", "description": "URL of the page to search in (optional if using after open)"}
+                    "url": {"type": "string", "description": "URL of the page to search in (optional if using after open)"},
+                    "cursor": {"type": "integer", "description": "Cursor of a page returned by open, instead of url"},
+                    "scope": {"type": "string", "enum": ["page", "session"], "description": "Search one page, or every page opened in this session (grouped by page, ranked by hit density)", "default": "page"},
+                    "regex": {"type": "boolean", "description": "Treat pattern as a regular expression", "default": false},
+                    "case_sensitive": {"type": "boolean", "description": "Match letter case exactly", "default": false},
+                    "whole_word": {"type": "boolean", "description": "Only match whole words", "default": false},
+                    "fuzzy": {"type": "boolean", "description": "Rank passages by relevance to the pattern's words (BM25) instead of matching it exactly; tolerates different wording and word forms", "default": false},
+                    "context_lines": {"type": "integer", "description": "Lines of context shown before and after each match; overlapping windows are merged", "default": 2, "minimum": 0, "maximum": 20},
+                    "max_matches": {"type": "integer", "description": "Maximum matches to return (per page with scope session, default 3)", "default": 10, "minimum": 1, "maximum": 100},
+                    "offset": {"type": "integer", "description": "Number of matches to skip, to continue a previous find", "default": 0, "minimum": 0}
                 },
                 "required": ["pattern"]
             }
         }));
         
+        tools.insert("extract_tables", json!({
+            "name": "extract_tables",
+            "description": "Extract HTML tables from a page as rows and columns, with header detection and merged cells expanded",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "url": {"type": "string", "description": "URL of the page (optional if using after open; fetched if not opened yet)"},
+                    "cursor": {"type": "number", "description": "Cursor of a page returned by open (alternative to url)"},
+                    "index": {"type": "number", "description": "Index of a single table to return (tables are numbered from 0 in page order)"},
+                    "format": {"type": "string", "enum": ["markdown", "csv"], "description": "Text format for the tables", "default": "markdown"},
+                    "max_rows": {"type": "number", "description": "Maximum rows to return per table", "default": 50}
+                }
+            }
+        }));
+        
+        tools.insert("select", json!({
+            "name": "select",
+            "description": "Return the elements of a page matching a CSS selector, with their text, attributes and HTML",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "selector": {"type": "string", "description": "CSS selector, e.g. \".release-notes\", \"h2\", \"a[href$='.pdf']\""},
+                    "url": {"type": "string", "description": "URL of the page (optional if using after open; fetched if not opened yet)"},
+                    "cursor": {"type": "number", "description": "Cursor of a page returned by open (alternative to url)"},
+                    "max_results": {"type": "number", "description": "Maximum elements to return", "default": 20, "minimum": 1},
+                    "max_html_chars": {"type": "number", "description": "Each element's text and outer HTML are cut after this many characters", "default": 2000}
+                },
+                "required": ["selector"]
+            }
+        }));
+
+        for (name, description) in [
+            ("back", "Go back to the page opened before the current one in this session, as rendered then"),
+            ("forward", "Go forward again after back"),
+        ] {
+            tools.insert(name, json!({
+                "name": name,
+                "description": description,
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "steps": {"type": "number", "description": "Number of pages to move", "default": 1, "minimum": 1},
+                        "loc": {"type": "number", "description": "Starting line number (default: 0)", "default": 0},
+                        "num_lines": {"type": "number", "description": "Number of lines to show (-1 for all)", "default": -1},
+                        "max_tokens": {"type": "integer", "description": "Token budget for the lines shown; 0 for no limit. Defaults to the server's limit (10000)", "minimum": 0}
+                    }
+                }
+            }));
+        }
+
+        tools.insert("reload", json!({
+            "name": "reload",
+            "description": "Fetch a page again from the network, replacing the copy kept in the session, and show it",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "url": {"type": "string", "description": "URL to reload (default: the current page)"},
+                    "loc": {"type": "number", "description": "Starting line number (default: 0)", "default": 0},
+                    "num_lines": {"type": "number", "description": "Number of lines to show (-1 for all)", "default": -1},
+                    "max_tokens": {"type": "integer", "description": "Token budget for the lines shown; 0 for no limit. Defaults to the server's limit (10000)", "minimum": 0},
+                    "mode": {"type": "string", "enum": ["article", "full"], "description": "\"article\" shows only the main content with title, byline and date; \"full\" shows the whole page (default: as last shown)"},
+                    "format": {"type": "string", "enum": ["text", "markdown"], "description": "\"markdown\" keeps headings, fenced code blocks, tables and nested lists (default: as last shown)"},
+                    "wrap": {"type": "number", "description": "Column to wrap prose at, 0 for no wrapping (default: as last shown)"}
+                }
+            }
+        }));
+
+        tools.insert("open_many", json!({
+            "name": "open_many",
+            "description": "Open several URLs in one call, fetching them in parallel. Each page gets a cursor; the token and line budgets are shared across pages, and failures are reported per URL",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "urls": {"type": "array", "items": {"type": "string"}, "description": "URLs to open, at most 10", "minItems": 1, "maxItems": 10},
+                    "max_concurrency": {"type": "integer", "description": "Pages fetched at the same time (default: server setting, 4)", "minimum": 1, "maximum": 10},
+                    "max_tokens": {"type": "integer", "description": "Token budget shared by all pages (o200k_harmony tokens); 0 for no limit. Defaults to the server's limit (10000)", "minimum": 0},
+                    "num_lines": {"type": "number", "description": "Line budget shared by all pages (-1 for no limit)", "default": -1},
+                    "mode": {"type": "string", "enum": ["article", "full"], "description": "\"article\" shows only the main content with title, byline and date; \"full\" shows the whole page", "default": "article"},
+                    "format": {"type": "string", "enum": ["text", "markdown"], "description": "\"markdown\" keeps headings, fenced code blocks, tables and nested lists", "default": "text"},
+                    "wrap": {"type": "number", "description": "Column to wrap prose at (0 for no wrapping)", "default": 80}
+                },
+                "required": ["urls"]
+            }
+        }));
+
+        tools.insert("research", json!({
+            "name": "research",
+            "description": "Search the web, read the top results in parallel and return the passages of each most relevant to the query, with citations. Replaces a search, several opens and finds",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "Search query, also used to rank passages"},
+                    "num_results": {"type": "number", "description": "Number of top results to read", "default": 3, "minimum": 1, "maximum": 10},
+                    "passages": {"type": "number", "description": "Passages returned per result", "default": 2, "minimum": 1, "maximum": 5},
+                    "sites": {"type": "array", "items": {"type": "string"}, "description": "Only read results from these domains (e.g. [\"docs.rs\"])"},
+                    "exclude_sites": {"type": "array", "items": {"type": "string"}, "description": "Exclude results from these domains"},
+                    "time_range": {"type": "string", "enum": ["day", "week", "month", "year"], "description": "Only read results published within this period"},
+                    "region": {"type": "string", "description": "Two-letter country code to localize results (e.g. \"us\", \"de\")"},
+                    "language": {"type": "string", "description": "Two-letter language code for results (e.g. \"en\", \"de\")"},
+                    "safe_search": {"type": "string", "enum": ["off", "moderate", "strict"], "description": "Safe search level (backend default if omitted)"}
+                },
+                "required": ["query"]
+            }
+        }));
+
+        tools.insert("retrieve", json!({
+            "name": "retrieve",
+            "description": "Return the passages of a long page most relevant to a query. The page is split into overlapping chunks along headings and paragraphs and ranked with BM25; each passage comes with its line range for citation",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "What to look for, in natural language or keywords"},
+                    "url": {"type": "string", "description": "URL of the page (optional if using after open; fetched if not opened yet)"},
+                    "cursor": {"type": "number", "description": "Cursor of a page returned by open (alternative to url)"},
+                    "top_k": {"type": "number", "description": "Number of passages to return", "default": 5, "minimum": 1, "maximum": 20}
+                },
+                "required": ["query"]
+            }
+        }));
+        
         tools
     };
 }
 
+/// Names of the registered tools, sorted so status output is stable.
+fn tool_names() -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = TOOLS.keys().copied().collect();
+    names.sort_unstable();
+    names
+}
+
+/// Pages kept on a session's back stack.
+const MAX_HISTORY: usize = 100;
+
+/// How an `open` moves through the session history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Navigation {
+    /// A new page: the current one goes on the back stack.
+    Visit,
+    /// `back` or `forward`, which have already moved the stacks.
+    History,
+    /// A visit that fetches the page again instead of using the session.
+    Reload,
+}
+
 // Session state management for browser tools
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct BrowserSession {
     pub current_url: Option<String>,
     pub current_content: Option<String>,
-    pub pages: HashMap<String, String>,
+    pub pages: HashMap<String, Page>,
+    /// URLs in the order they were first loaded; a page's cursor is its
+    /// index here.
+    pub cursors: Vec<String>,
+    /// Pages visited before the current one, most recent last.
+    pub back: Vec<String>,
+    /// Pages left with `back`, next one last.
+    pub forward: Vec<String>,
 }
 
-impl Default for BrowserSession {
-    fn default() -> Self {
-        Self {
-            current_url: None,
-            current_content: None,
-            pages: HashMap::new(),
+impl BrowserSession {
+    /// Store `page` under `url` and return its cursor, a short handle that
+    /// later calls can use instead of the URL.
+    pub fn insert_page(&mut self, url: &str, page: Page) -> usize {
+        self.pages.insert(url.to_string(), page);
+        match self.cursor_of(url) {
+            Some(cursor) => cursor,
+            None => {
+                self.cursors.push(url.to_string());
+                self.cursors.len() - 1
+            }
+        }
+    }
+
+    pub fn cursor_of(&self, url: &str) -> Option<usize> {
+        self.cursors.iter().position(|u| u == url)
+    }
+
+    /// Record a move to `url`: as in a browser, the current page goes on
+    /// the back stack and the forward stack is cleared.
+    pub fn visit(&mut self, url: &str) {
+        if let Some(previous) = self.current_url.clone().filter(|p| p != url) {
+            self.back.push(previous);
+            if self.back.len() > MAX_HISTORY {
+                self.back.remove(0);
+            }
+            self.forward.clear();
+        }
+    }
+
+    /// Step `steps` pages back, moving the pages passed onto the forward
+    /// stack. Returns the URL to show, or `None` if history is too short.
+    pub fn go_back(&mut self, steps: usize) -> Option<String> {
+        Self::step(&mut self.back, &mut self.forward, self.current_url.as_deref(), steps)
+    }
+
+    pub fn go_forward(&mut self, steps: usize) -> Option<String> {
+        Self::step(&mut self.forward, &mut self.back, self.current_url.as_deref(), steps)
+    }
+
+    fn step(from: &mut Vec<String>, to: &mut Vec<String>, current: Option<&str>, steps: usize) -> Option<String> {
+        let current = current?;
+        if steps == 0 || from.len() < steps {
+            return None;
+        }
+        to.push(current.to_string());
+        for _ in 1..steps {
+            to.push(from.pop()?);
         }
+        from.pop()
     }
 }
 
+/// A fetched page. The source document is kept so the page can be
+/// re-rendered in another mode without refetching.
+#[derive(Debug, Clone, Default)]
+pub struct Page {
+    pub source: Source,
+    /// Encoding the source was transcoded from; `None` for PDFs.
+    pub charset: Option<Charset>,
+    /// Document metadata; HTML pages only.
+    pub metadata: Option<PageMetadata>,
+    pub rendered: RenderedPage,
+    /// Options `rendered` was produced with.
+    pub options: RenderOptions,
+}
+
 // Simple in-memory session storage
 lazy_static::lazy_static! {
     static ref SESSIONS: std::sync::RwLock<HashMap<String, BrowserSession>> = 
@@ -179,7 +479,7 @@ impl McpServer {
             "authentication": "OAuth/JWT ready",
             "aws_agentcore_compliant": true,
             "architecture": "ARM64 optimized",
-            "tools": ["search", "open", "find"],
+            "tools": tool_names(),
             "tools_count": TOOLS.len(),
             "status": "ready"
         })))
@@ -193,7 +493,8 @@ impl McpServer {
             "server": "GPT-OSS Browser MCP",
             "tools_loaded": TOOLS.len(),
             "version": "1.0.0",
-            "architecture": "ARM64"
+            "architecture": "ARM64",
+            "metrics": metrics::snapshot()
         })))
     }
 
@@ -296,7 +597,7 @@ impl McpServer {
                 "name": "gpt-oss-browser",
                 "version": "1.0.0"
             },
-            "instructions": "🌐 **GPT-OSS Browser MCP Server**\n\nBrowser tools from the GPT-OSS project for web searching and content analysis.\n\n**🛠️ Available Tools:**\n- **search**: Search for information on the web with citations\n- **open**: Open web pages and view content with line numbers\n- **find**: Find text patterns in opened pages\n\n**🔧 Features:**\n- Full MCP 2024-11-05 protocol compliance\n- Session-based browsing state\n- HTML to text conversion\n- Citation support with line numbers\n- ARM64 optimized for AWS Lambda Graviton\n\n**💡 Usage Tips:**\n- Use search to find relevant web content\n- Open URLs to view full page content\n- Use find to locate specific information within pages\n- Sessions maintain browsing history for context"
+            "instructions": "🌐 **GPT-OSS Browser MCP Server**\n\nBrowser tools from the GPT-OSS project for web searching and content analysis.\n\n**🛠️ Available Tools:**\n- **search**: Search for information on the web with citations\n- **open**: Open web pages and view content with line numbers\n- **open_many**: Open several pages in parallel under a shared budget\n- **back** / **forward**: Move through the pages opened in this session\n- **reload**: Fetch a page again, bypassing the session copy\n- **find**: Find text patterns in opened pages\n- **extract_tables**: Extract page tables as rows and columns (Markdown or CSV)\n- **select**: Extract elements matching a CSS selector\n- **retrieve**: Return the passages of a page most relevant to a query\n- **research**: Search, read the top results and return cited passages in one call\n\n**🔧 Features:**\n- Full MCP 2024-11-05 protocol compliance\n- Session-based browsing state\n- HTML to text conversion\n- Citation support with line numbers\n- ARM64 optimized for AWS Lambda Graviton\n\n**💡 Usage Tips:**\n- Use search to find relevant web content\n- Open URLs to view full page content\n- Use find to locate specific information within pages\n- Sessions maintain browsing history for context"
         });
 
         info!("✅ MCP initialization successful");
@@ -315,7 +616,7 @@ impl McpServer {
             "server": "gpt-oss-browser",
             "version": "1.0.0",
             "uptime": "running",
-            "tools_available": 3,
+            "tools_available": TOOLS.len(),
             "protocol": "MCP",
             "architecture": "ARM64",
             "message": "pong"
@@ -431,13 +732,14 @@ impl McpServer {
         let result = Self::execute_tool(tool_name, &arguments, session_id).await;
         
         match result {
-            Ok(content) => {
+            Ok(output) => {
                 info!("✅ Tool {} executed successfully", tool_name);
                 let mcp_result = McpToolResult {
                     content: vec![McpContent {
                         content_type: "text".to_string(),
-                        text: content,
+                        text: output.text,
                     }],
+                    structured_content: output.structured,
                 };
                 Ok(ResponseJson(Self::create_success_response(request.id, json!(mcp_result))))
             }
@@ -453,7 +755,7 @@ impl McpServer {
         }
     }
 
-    async fn execute_tool(tool_name: &str, arguments: &Value, session_id: &str) -> Result<String, String> {
+    async fn execute_tool(tool_name: &str, arguments: &Value, session_id: &str) -> Result<ToolOutput, String> {
         let client = reqwest::Client::builder()
             .user_agent("Mozilla/5.0 (compatible; GPT-OSS-Browser/1.0.0)")
             .timeout(std::time::Duration::from_secs(30))
@@ -461,13 +763,38 @@ impl McpServer {
             .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
 
         match tool_name {
-            "search" => Self::execute_search(&client, arguments).await,
+            "search" => Self::execute_search(&client, arguments).await.map(ToolOutput::from),
             "open" => Self::execute_open(&client, arguments, session_id).await,
-            "find" => Self::execute_find(arguments, session_id).await,
+            "open_many" => Self::execute_open_many(&client, arguments, session_id).await,
+            "back" => Self::execute_history(&client, arguments, session_id, false).await,
+            "forward" => Self::execute_history(&client, arguments, session_id, true).await,
+            "reload" => Self::execute_reload(&client, arguments, session_id).await,
+            "find" => Self::execute_find(arguments, session_id).await.map(ToolOutput::from),
+            "extract_tables" => Self::execute_extract_tables(&client, arguments, session_id).await,
+            "select" => Self::execute_select(&client, arguments, session_id).await,
+            "retrieve" => Self::execute_retrieve(&client, arguments, session_id).await,
+            "research" => Self::execute_research(&client, arguments, session_id).await,
             _ => Err(format!("Unknown tool: {}", tool_name)),
         }
     }
 
+    /// First result to return: a `cursor` from an earlier search wins over
+    /// an explicit `offset`, which wins over a 1-based `page` of `topn`.
+    fn search_offset(arguments: &Value, topn: u64, backend: &str) -> Result<usize, String> {
+        if let Some(cursor) = arguments.get("cursor").and_then(|v| v.as_str()) {
+            search::parse_continuation_token(cursor, backend)
+                .ok_or_else(|| format!("❌ Invalid cursor: '{}'. Use the cursor value returned by a previous search with the {} backend.", cursor, backend))
+        } else if let Some(offset) = arguments.get("offset").and_then(|v| v.as_u64()) {
+            Ok(offset as usize)
+        } else {
+            let page = arguments.get("page")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(1)
+                .max(1);
+            Ok(((page - 1) * topn) as usize)
+        }
+    }
+
     async fn execute_search(client: &reqwest::Client, arguments: &Value) -> Result<String, String> {
         let query = arguments.get("query")
             .and_then(|v| v.as_str())
@@ -480,54 +807,77 @@ impl McpServer {
         let topn = arguments.get("topn")
             .and_then(|v| v.as_u64())
             .unwrap_or(10)
-            .min(50)
-            .max(1);
+            .clamp(1, 50);
 
-        info!("🔍 Searching web for: '{}', limit: {}", query, topn);
+        let backend = search::backend_from_env().map_err(|e| e.to_string())?;
+        let offset = Self::search_offset(arguments, topn, backend.name())?;
 
-        // For this implementation, we'll use DuckDuckGo's instant answer API
-        // In a real implementation, you would integrate with Exa or other search APIs
-        let search_url = format!(
-            "https://html.duckduckgo.com/html/?q={}",
-            urlencoding::encode(query)
-        );
+        let filters = Self::parse_search_filters(arguments)?;
 
-        let response = client.get(&search_url)
-            .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
-            .send()
-            .await
-            .map_err(|e| format!("Network error while searching: {}", e))?;
-
-        if !response.status().is_success() {
-            return Err(format!("❌ Search request failed with status: {}\n\nThis might be a temporary issue. Please try again later.", response.status()));
-        }
-
-        let html = response.text().await
-            .map_err(|e| format!("Error reading search response: {}", e))?;
+        let vertical: Vertical = arguments.get("type")
+            .or_else(|| arguments.get("vertical"))
+            .and_then(|v| v.as_str())
+            .map(|v| v.parse())
+            .transpose()?
+            .unwrap_or_default();
+
+        info!("🔍 Searching {} via {} for: '{}', limit: {}, offset: {}", vertical.as_str(), backend.name(), query, topn, offset);
+
+        let search_query = SearchQuery {
+            query: query.to_string(),
+            limit: topn as usize,
+            offset,
+            filters,
+            vertical,
+        };
 
-        // Parse the search results from DuckDuckGo HTML
-        let results = Self::parse_search_results(&html, topn as usize)?;
+        let page = backend.search(client, &search_query).await
+            .map_err(|e| e.to_string())?;
+        let results = &page.results;
 
         if results.is_empty() {
             return Ok(format!("🔍 No results found for query: \"{}\"\n\n💡 **Suggestions:**\n- Try different search terms\n- Check spelling\n- Use more general terms", query));
         }
 
-        let mut formatted_results = format!("🔍 **Search Results for \"{}\":**\n\n", query);
+        let heading = match vertical {
+            Vertical::Web => "Search Results",
+            Vertical::News => "News Results",
+            Vertical::Images => "Image Results",
+            Vertical::Videos => "Video Results",
+        };
+        let mut formatted_results = format!("🔍 **{} for \"{}\":**\n\n", heading, query);
         
-        for (index, (title, url, snippet)) in results.iter().enumerate() {
+        for (index, result) in results.iter().enumerate() {
             formatted_results.push_str(&format!(
                 "**{}. {}**\n",
-                index + 1,
-                title
+                offset + index + 1,
+                result.title
             ));
             
-            if !snippet.is_empty() {
-                formatted_results.push_str(&format!("   {}\n", snippet));
+            if !result.snippet.is_empty() {
+                formatted_results.push_str(&format!("   {}\n", result.snippet));
             }
+
+            Self::push_result_details(&mut formatted_results, result);
             
             formatted_results.push_str(&format!(
                 "   🔗 {}\n\n",
-                url
+                result.url
+            ));
+        }
+
+        if !page.warnings.is_empty() {
+            formatted_results.push_str("⚠️ **Some engines were unavailable:**\n");
+            for warning in &page.warnings {
+                formatted_results.push_str(&format!("- {}\n", warning));
+            }
+            formatted_results.push('\n');
+        }
+
+        if let Some(next_offset) = page.next_offset {
+            formatted_results.push_str(&format!(
+                "➡️ **More results available.** Continue with cursor: `{}`\n\n",
+                search::continuation_token(backend.name(), next_offset)
             ));
         }
 
@@ -538,43 +888,164 @@ impl McpServer {
         Ok(formatted_results)
     }
 
-    fn parse_search_results(html: &str, limit: usize) -> Result<Vec<(String, String, String)>, String> {
-        let document = Html::parse_document(html);
-        let result_selector = Selector::parse("div.result").map_err(|e| format!("CSS selector error: {}", e))?;
-        let title_selector = Selector::parse("a.result__a").map_err(|e| format!("CSS selector error: {}", e))?;
-        let snippet_selector = Selector::parse("a.result__snippet").map_err(|e| format!("CSS selector error: {}", e))?;
+    /// Structured fields returned by the news, image and video verticals.
+    fn push_result_details(output: &mut String, result: &SearchResult) {
+        let byline: Vec<&str> = [result.publisher.as_deref(), result.published.as_deref(), result.duration.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if !byline.is_empty() {
+            output.push_str(&format!("   📰 {}\n", byline.join(" · ")));
+        }
 
-        let mut results = Vec::new();
+        if let Some(image_url) = &result.image_url {
+            output.push_str(&format!("   🖼️ Image: {}", image_url));
+            if let (Some(width), Some(height)) = (result.width, result.height) {
+                output.push_str(&format!(" ({}×{})", width, height));
+            }
+            output.push('\n');
+            if !result.title.is_empty() {
+                output.push_str(&format!("   Alt text: {}\n", result.title));
+            }
+        }
+
+        if let Some(thumbnail) = &result.thumbnail {
+            output.push_str(&format!("   Thumbnail: {}\n", thumbnail));
+        }
 
-        for result in document.select(&result_selector).take(limit) {
-            let title = result
-                .select(&title_selector)
-                .next()
-                .map(|el| el.text().collect::<String>().trim().to_string())
-                .unwrap_or_else(|| "Untitled".to_string());
+        if !result.engines.is_empty() {
+            output.push_str(&format!("   🔎 Engines: {}\n", result.engines.join(", ")));
+        }
+    }
 
-            let url = result
-                .select(&title_selector)
-                .next()
-                .and_then(|el| el.value().attr("href"))
-                .unwrap_or("")
-                .to_string();
+    fn parse_search_filters(arguments: &Value) -> Result<SearchFilters, String> {
+        let string_list = |key: &str| -> Vec<String> {
+            arguments.get(key)
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items.iter()
+                        .filter_map(|v| v.as_str())
+                        .map(SearchFilters::normalize_site)
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let code = |key: &str| -> Option<String> {
+            arguments.get(key)
+                .and_then(|v| v.as_str())
+                .map(|v| v.trim().to_lowercase())
+                .filter(|v| !v.is_empty())
+        };
 
-            let snippet = result
-                .select(&snippet_selector)
-                .next()
-                .map(|el| el.text().collect::<String>().trim().to_string())
-                .unwrap_or_else(|| String::new());
+        let time_range = code("time_range")
+            .map(|v| v.parse())
+            .transpose()?;
 
-            if !url.is_empty() {
-                results.push((title, url, snippet));
+        let safe_search = match arguments.get("safe_search") {
+            Some(Value::Bool(true)) => Some(search::SafeSearch::Strict),
+            Some(Value::Bool(false)) => Some(search::SafeSearch::Off),
+            Some(Value::String(v)) => Some(v.parse()?),
+            _ => None,
+        };
+
+        Ok(SearchFilters {
+            sites: string_list("sites"),
+            exclude_sites: string_list("exclude_sites"),
+            time_range,
+            region: code("region"),
+            language: code("language"),
+            safe_search,
+        })
+    }
+
+    async fn execute_open(client: &reqwest::Client, arguments: &Value, session_id: &str) -> Result<ToolOutput, String> {
+        Self::navigate(client, arguments, session_id, Navigation::Visit).await
+    }
+
+    /// `back` and `forward`: step through the session history and show the
+    /// page there, rendered as it was last shown.
+    async fn execute_history(client: &reqwest::Client, arguments: &Value, session_id: &str, forward: bool) -> Result<ToolOutput, String> {
+        let steps = arguments.get("steps")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1)
+            .max(1) as usize;
+        let direction = if forward { "forward" } else { "back" };
+
+        // Stepping moves pages between the stacks before the page is loaded,
+        // so keep the old stacks to put back if loading it fails.
+        let (url, saved) = {
+            let mut sessions = SESSIONS.write().map_err(|e| format!("Session lock error: {}", e))?;
+            let session = sessions.get_mut(session_id).ok_or("❌ No active session found.")?;
+            let saved = (session.back.clone(), session.forward.clone());
+            let target = if forward { session.go_forward(steps) } else { session.go_back(steps) };
+            let available = if forward { session.forward.len() } else { session.back.len() };
+            target.ok_or_else(|| format!(
+                "❌ Cannot go {} {} page(s): the history has {} page(s) in that direction.",
+                direction, steps, available
+            )).map(|url| (url, saved))?
+        };
+
+        info!("🧭 Going {} {} page(s) to {}", direction, steps, url);
+
+        let arguments = Self::with_url(arguments, &url);
+        let mut output = match Self::navigate(client, &arguments, session_id, Navigation::History).await {
+            Ok(output) => output,
+            Err(e) => {
+                let mut sessions = SESSIONS.write().map_err(|e| format!("Session lock error: {}", e))?;
+                if let Some(session) = sessions.get_mut(session_id) {
+                    (session.back, session.forward) = saved;
+                }
+                return Err(e);
             }
-        }
+        };
+        let arrow = if forward { "➡️" } else { "⬅️" };
+        output.text = format!("{} Went {} {} page(s).\n\n{}", arrow, direction, steps, output.text);
+        Ok(output)
+    }
+
+    /// Fetch a page again, bypassing the copy kept in the session.
+    async fn execute_reload(client: &reqwest::Client, arguments: &Value, session_id: &str) -> Result<ToolOutput, String> {
+        let url = match arguments.get("url").and_then(|v| v.as_str()).filter(|u| !u.trim().is_empty()) {
+            Some(url) => url.to_string(),
+            None => {
+                let sessions = SESSIONS.read().map_err(|e| format!("Session lock error: {}", e))?;
+                sessions.get(session_id)
+                    .and_then(|s| s.current_url.clone())
+                    .ok_or("❌ No page is currently open.\nPlease open a page first using the 'open' tool, or pass a url.")?
+            }
+        };
+
+        info!("🔄 Reloading {}", url);
+
+        let arguments = Self::with_url(arguments, &url);
+        let mut output = Self::navigate(client, &arguments, session_id, Navigation::Reload).await?;
+        output.text = format!("🔄 Reloaded from the network.\n\n{}", output.text);
+        Ok(output)
+    }
 
-        Ok(results)
+    /// Tool arguments with `url` set.
+    fn with_url(arguments: &Value, url: &str) -> Value {
+        let mut arguments = arguments.as_object().cloned().unwrap_or_default();
+        arguments.insert("url".to_string(), json!(url));
+        Value::Object(arguments)
     }
 
-    async fn execute_open(client: &reqwest::Client, arguments: &Value, session_id: &str) -> Result<String, String> {
+    /// Render options for a page shown again through the history or a
+    /// reload: the ones given, else those it was last shown with, so line
+    /// numbers stay the same.
+    fn revisit_options(arguments: &Value, url: &str, session_id: &str) -> Result<RenderOptions, String> {
+        if ["mode", "format", "wrap"].iter().any(|key| arguments.get(*key).is_some_and(|v| !v.is_null())) {
+            return Self::parse_render_options(arguments);
+        }
+        let sessions = SESSIONS.read().map_err(|e| format!("Session lock error: {}", e))?;
+        Ok(sessions.get(session_id)
+            .and_then(|s| s.pages.get(url))
+            .map(|page| page.options)
+            .unwrap_or_default())
+    }
+
+    async fn navigate(client: &reqwest::Client, arguments: &Value, session_id: &str, navigation: Navigation) -> Result<ToolOutput, String> {
         let url = arguments.get("url")
             .and_then(|v| v.as_str())
             .ok_or("Missing required parameter: url")?;
@@ -591,32 +1062,21 @@ impl McpServer {
             .and_then(|v| v.as_i64())
             .unwrap_or(-1);
 
-        info!("📂 Opening URL: {} (loc: {}, num_lines: {})", url, loc, num_lines);
+        let max_tokens = Self::parse_max_tokens(arguments)?;
 
-        // Check if we already have this page in our session
-        let session_data = {
-            let sessions = SESSIONS.read().map_err(|e| format!("Session lock error: {}", e))?;
-            sessions.get(session_id).cloned()
+        let options = match navigation {
+            Navigation::Visit => Self::parse_render_options(arguments)?,
+            Navigation::History | Navigation::Reload => Self::revisit_options(arguments, url, session_id)?,
         };
+        let mode = options.mode;
 
-        let content = if let Some(session) = session_data {
-            if let Some(cached_content) = session.pages.get(url) {
-                cached_content.clone()
-            } else {
-                Self::fetch_page_content(client, url).await?
-            }
-        } else {
-            Self::fetch_page_content(client, url).await?
-        };
+        info!("📂 Opening URL: {} (loc: {}, num_lines: {}, max_tokens: {:?}, mode: {}, format: {})", url, loc, num_lines, max_tokens, mode.as_str(), options.format.as_str());
 
-        // Update session with the new page
-        {
-            let mut sessions = SESSIONS.write().map_err(|e| format!("Session lock error: {}", e))?;
-            let session = sessions.entry(session_id.to_string()).or_default();
-            session.current_url = Some(url.to_string());
-            session.current_content = Some(content.clone());
-            session.pages.insert(url.to_string(), content.clone());
-        }
+        let refresh = navigation == Navigation::Reload;
+        let (page, cursor) = Self::open_page(client, url, &options, session_id, refresh).await?;
+        let rendered = page.rendered.clone();
+        let content = rendered.text.clone();
+        Self::set_current_page(session_id, url, &content, navigation != Navigation::History)?;
 
         // Format content with line numbers
         let lines: Vec<&str> = content.lines().collect();
@@ -629,39 +1089,333 @@ impl McpServer {
             ));
         }
 
-        let end_loc = if num_lines == -1 {
+        let line_limit = if num_lines == -1 {
             total_lines
         } else {
             (loc + num_lines as usize).min(total_lines)
         };
 
-        let lines_to_show = &lines[loc..end_loc];
-        let mut result = format!("📄 **{}**\n\n", url);
-        
+        // Lines as they will be shown, so the budget counts the prefixes too
+        let numbered: Vec<String> = lines[loc..line_limit]
+            .iter()
+            .enumerate()
+            .map(|(i, line)| format!("L{}: {}\n", loc + i, line))
+            .collect();
+
+        // Metadata accompanies the first chunk only, not every continuation.
+        // It counts against the budget and may take up to a quarter of it.
+        let metadata_lines: Vec<String> = page.metadata.as_ref()
+            .filter(|m| loc == 0 && !m.is_empty())
+            .map(|m| m.summary().iter().map(|line| format!("\n- {}", line)).collect())
+            .unwrap_or_default();
+        let metadata_header = "\n\n🏷️ **Metadata:**";
+        let (metadata_shown, metadata_tokens) = match max_tokens {
+            _ if metadata_lines.is_empty() => (0, 0),
+            Some(budget) => match tokens::fit_within(&metadata_lines, (budget / 4).saturating_sub(tokens::count(metadata_header))) {
+                (0, _) => (0, 0),
+                (n, used) => (n, used + tokens::count(metadata_header)),
+            },
+            None => (metadata_lines.len(), tokens::count(metadata_header) + metadata_lines.iter().map(|line| tokens::count(line)).sum::<usize>()),
+        };
+
+        let (shown, shown_tokens) = match max_tokens {
+            Some(budget) => tokens::fit_lines(&numbered, budget - metadata_tokens),
+            None => (numbered.len(), numbered.iter().map(|line| tokens::count(line)).sum()),
+        };
+        let end_loc = loc + shown;
+        let total_tokens = tokens::count(&content);
+        let title = rendered.title.clone()
+            .or_else(|| page.metadata.as_ref().and_then(|m| m.title.clone()));
+        let mut result = match &title {
+            Some(title) => format!("📄 **{}** (cursor {})\n{}\n", title, cursor, url),
+            None => format!("📄 **{}** (cursor {})\n", url, cursor),
+        };
+        if let Some(byline) = &rendered.byline {
+            result.push_str(&format!("✍️ {}\n", byline));
+        }
+        if let Some(published) = &rendered.published {
+            result.push_str(&format!("📅 {}\n", published));
+        }
+        match &page.source {
+            Source::Pdf { pages, .. } => result.push_str(&format!("📑 PDF, {} page(s)\n", pages)),
+            Source::Text { kind, .. } => result.push_str(&format!("📃 {}\n", kind.label())),
+            Source::Html(_) if mode == ContentMode::Article && rendered.mode == ContentMode::Full => {
+                result.push_str("💡 No main article found; showing the full page.\n");
+            }
+            Source::Html(_) => {}
+        }
+        result.push('\n');
+
         if loc > 0 {
             result.push_str(&format!("📄 [Starting from line {}]\n\n", loc));
         }
 
-        for (i, line) in lines_to_show.iter().enumerate() {
-            result.push_str(&format!("L{}: {}\n", loc + i, line));
+        for line in &numbered[..shown] {
+            result.push_str(line);
         }
 
         if end_loc < total_lines {
+            let reason = match max_tokens {
+                Some(budget) if end_loc < line_limit => format!(" (token budget of {} reached)", budget),
+                _ => String::new(),
+            };
             result.push_str(&format!(
-                "\n📄 [Content truncated at line {} of {}. Use loc parameter to continue reading.]",
-                end_loc.saturating_sub(1), total_lines.saturating_sub(1)
+                "\n📄 [Content truncated at line {} of {}{}. Continue reading with loc: {}.]",
+                end_loc.saturating_sub(1), total_lines.saturating_sub(1), reason, end_loc
             ));
         }
 
         result.push_str(&format!("\n\n🔗 **URL:** {}", url));
-        result.push_str(&format!("\n📊 **Stats:** {} lines total", total_lines));
+        match &page.source {
+            Source::Pdf { pages, .. } => result.push_str(&format!("\n📊 **Stats:** {} lines, {} tokens total, {} page(s)", total_lines, total_tokens, pages)),
+            Source::Text { kind, .. } => result.push_str(&format!("\n📊 **Stats:** {} lines, {} tokens total ({})", total_lines, total_tokens, kind.label())),
+            Source::Html(_) => result.push_str(&format!("\n📊 **Stats:** {} lines, {} tokens total ({} mode)", total_lines, total_tokens, rendered.mode.as_str())),
+        }
+        if let Some(charset) = page.charset {
+            result.push_str(&format!("\n🔤 **Encoding:** {} ({})", charset.name(), charset.source.as_str()));
+        }
 
-        Ok(result)
+        if metadata_shown > 0 {
+            result.push_str(metadata_header);
+            for line in &metadata_lines[..metadata_shown] {
+                result.push_str(line);
+            }
+        }
+        if metadata_shown < metadata_lines.len() {
+            result.push_str(&format!(
+                "\n\n🏷️ {} metadata line(s) left out to stay within the token budget; see structuredContent.",
+                metadata_lines.len() - metadata_shown
+            ));
+        }
+
+        let content_type = match &page.source {
+            Source::Html(_) => "html",
+            Source::Text { .. } => "text",
+            Source::Pdf { .. } => "pdf",
+        };
+        let structured = json!({
+            "url": url,
+            "cursor": cursor,
+            "title": title,
+            "content_type": content_type,
+            "encoding": page.charset.map(|c| c.name()),
+            "mode": rendered.mode.as_str(),
+            "lines": {"start": loc, "end": end_loc, "total": total_lines},
+            "tokens": {"shown": shown_tokens + metadata_tokens, "total": total_tokens, "budget": max_tokens},
+            "next_loc": (end_loc < total_lines).then_some(end_loc),
+            "byline": rendered.byline,
+            "metadata": page.metadata,
+        });
+
+        Ok(ToolOutput { text: result, structured: Some(structured) })
     }
 
-    async fn fetch_page_content(client: &reqwest::Client, url: &str) -> Result<String, String> {
-        let response = client.get(url)
-            .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
+    async fn execute_open_many(client: &reqwest::Client, arguments: &Value, session_id: &str) -> Result<ToolOutput, String> {
+        let mut urls: Vec<String> = Vec::new();
+        for url in arguments.get("urls")
+            .and_then(|v| v.as_array())
+            .ok_or("Missing required parameter: urls")?
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+        {
+            if !urls.iter().any(|u| u == url) {
+                urls.push(url.to_string());
+            }
+        }
+        if urls.is_empty() {
+            return Err("❌ Error: urls must list at least one URL.".to_string());
+        }
+        if urls.len() > MAX_OPEN_MANY_URLS {
+            return Err(format!("❌ Error: at most {} URLs can be opened at once ({} given).", MAX_OPEN_MANY_URLS, urls.len()));
+        }
+
+        let concurrency = arguments.get("max_concurrency")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).clamp(1, MAX_OPEN_MANY_URLS))
+            .unwrap_or_else(default_open_concurrency);
+        let max_tokens = Self::parse_max_tokens(arguments)?;
+        let max_lines = arguments.get("num_lines")
+            .and_then(|v| v.as_i64())
+            .filter(|n| *n >= 0)
+            .map(|n| n as usize);
+        let options = Self::parse_render_options(arguments)?;
+
+        info!("📚 Opening {} URLs (concurrency: {}, max_tokens: {:?}, num_lines: {:?})", urls.len(), concurrency, max_tokens, max_lines);
+
+        let outcomes: Vec<Result<(Page, usize), String>> = stream::iter(urls.clone())
+            .map(|url| {
+                let options = &options;
+                async move { Self::open_page(client, &url, options, session_id, false).await }
+            })
+            .buffered(concurrency)
+            .collect()
+            .await;
+
+        // Lines of each opened page as they will be shown
+        let opened: Vec<(&String, &Page, usize, Vec<String>)> = urls.iter()
+            .zip(&outcomes)
+            .filter_map(|(url, outcome)| {
+                let (page, cursor) = outcome.as_ref().ok()?;
+                let numbered = page.rendered.text.lines()
+                    .enumerate()
+                    .map(|(i, line)| format!("L{}: {}\n", i, line))
+                    .collect();
+                Some((url, page, *cursor, numbered))
+            })
+            .collect();
+
+        // The budgets are shared: pages that need less leave more for the rest
+        let line_shares = match max_lines {
+            Some(budget) => tokens::split_budget(&opened.iter().map(|(.., n)| n.len()).collect::<Vec<_>>(), budget),
+            None => opened.iter().map(|(.., n)| n.len()).collect(),
+        };
+        let page_tokens: Vec<usize> = opened.iter()
+            .zip(&line_shares)
+            .map(|((.., n), &share)| n[..share].iter().map(|line| tokens::count(line)).sum())
+            .collect();
+        let token_shares = match max_tokens {
+            Some(budget) => tokens::split_budget(&page_tokens, budget),
+            None => page_tokens.clone(),
+        };
+
+        if let Some((url, page, ..)) = opened.first() {
+            Self::set_current_page(session_id, url, &page.rendered.text, true)?;
+        }
+
+        let mut result = format!("📚 **Opened {} of {} page(s)**", opened.len(), urls.len());
+        match (max_tokens, max_lines) {
+            (Some(tokens), Some(lines)) => result.push_str(&format!(" (sharing {} tokens and {} lines)", tokens, lines)),
+            (Some(tokens), None) => result.push_str(&format!(" (sharing {} tokens)", tokens)),
+            (None, Some(lines)) => result.push_str(&format!(" (sharing {} lines)", lines)),
+            (None, None) => {}
+        }
+        result.push('\n');
+
+        let mut pages = Vec::new();
+        let mut opened = opened.into_iter().zip(line_shares.into_iter().zip(token_shares));
+        for (url, outcome) in urls.iter().zip(&outcomes) {
+            if let Err(e) = outcome {
+                result.push_str(&format!("\n## ❌ {}\n{}\n", url, e));
+                pages.push(json!({"url": url, "error": e}));
+                continue;
+            }
+            let Some(((_, page, cursor, numbered), (line_share, token_share))) = opened.next() else { break };
+
+            let (shown, shown_tokens) = tokens::fit_within(&numbered[..line_share], token_share);
+            let total_lines = numbered.len();
+            let total_tokens = tokens::count(&page.rendered.text);
+            let title = page.rendered.title.clone()
+                .or_else(|| page.metadata.as_ref().and_then(|m| m.title.clone()));
+
+            result.push_str(&format!("\n## [cursor {}] {}\n🔗 {}\n\n", cursor, title.as_deref().unwrap_or(url), url));
+            for line in &numbered[..shown] {
+                result.push_str(line);
+            }
+            if shown < total_lines {
+                result.push_str(&format!(
+                    "\n📄 [Showing {} of {} lines. Continue reading with open, loc: {}.]\n",
+                    shown, total_lines, shown
+                ));
+            }
+            result.push_str(&format!("📊 {} lines, {} tokens total\n", total_lines, total_tokens));
+
+            pages.push(json!({
+                "url": url,
+                "cursor": cursor,
+                "title": title,
+                "lines": {"start": 0, "end": shown, "total": total_lines},
+                "tokens": {"shown": shown_tokens, "total": total_tokens},
+                "next_loc": (shown < total_lines).then_some(shown),
+            }));
+        }
+
+        Ok(ToolOutput {
+            text: result.trim_end().to_string(),
+            structured: Some(json!({
+                "pages": pages,
+                "budget": {"tokens": max_tokens, "lines": max_lines},
+            })),
+        })
+    }
+
+    /// Load `url` from the session, or fetch it, render it with `options`
+    /// and store it in the session. Returns the page and its cursor.
+    /// `refresh` skips the session copy.
+    async fn open_page(client: &reqwest::Client, url: &str, options: &RenderOptions, session_id: &str, refresh: bool) -> Result<(Page, usize), String> {
+        // Check if we already have this page in our session
+        let cached = if refresh {
+            None
+        } else {
+            let sessions = SESSIONS.read().map_err(|e| format!("Session lock error: {}", e))?;
+            sessions.get(session_id)
+                .and_then(|session| session.pages.get(url))
+                .cloned()
+        };
+
+        let mut page = match cached {
+            Some(page) => page,
+            None => Self::fetch_page_content(client, url).await?,
+        };
+        page.rendered = content::render(&page.source, options, url)?;
+        page.options = *options;
+
+        let mut sessions = SESSIONS.write().map_err(|e| format!("Session lock error: {}", e))?;
+        let session = sessions.entry(session_id.to_string()).or_default();
+        let cursor = session.insert_page(url, page.clone());
+        Ok((page, cursor))
+    }
+
+    /// Make `url` the current page; `record` adds the move to the history.
+    fn set_current_page(session_id: &str, url: &str, content: &str, record: bool) -> Result<(), String> {
+        let mut sessions = SESSIONS.write().map_err(|e| format!("Session lock error: {}", e))?;
+        let session = sessions.entry(session_id.to_string()).or_default();
+        if record {
+            session.visit(url);
+        }
+        session.current_url = Some(url.to_string());
+        session.current_content = Some(content.to_string());
+        Ok(())
+    }
+
+    /// `max_tokens` argument, falling back to the server default.
+    fn parse_max_tokens(arguments: &Value) -> Result<Option<usize>, String> {
+        match arguments.get("max_tokens").filter(|v| !v.is_null()) {
+            Some(value) => match value.as_u64() {
+                Some(0) => Ok(None),
+                Some(budget) => Ok(Some(budget as usize)),
+                None => Err(format!("❌ Invalid max_tokens: {}. Use a token count, or 0 for no limit.", value)),
+            },
+            None => Ok(tokens::default_max_tokens()),
+        }
+    }
+
+    fn parse_render_options(arguments: &Value) -> Result<RenderOptions, String> {
+        let mut options = RenderOptions::default();
+
+        if let Some(mode) = arguments.get("mode").and_then(|v| v.as_str()) {
+            options.mode = mode.parse()?;
+        }
+        if let Some(format) = arguments.get("format").and_then(|v| v.as_str()) {
+            options.format = format.parse()?;
+        }
+        if let Some(wrap) = arguments.get("wrap").filter(|v| !v.is_null()) {
+            options.wrap = match wrap.as_u64() {
+                Some(0) => None,
+                Some(width) => Some(width as usize),
+                None => return Err(format!("❌ Invalid wrap: {}. Use a column count, or 0 for no wrapping.", wrap)),
+            };
+        }
+
+        Ok(options)
+    }
+
+    /// Fetch a page and keep it in source form: HTML or text transcoded to
+    /// UTF-8, or extracted text for PDFs. The page is not rendered yet.
+    async fn fetch_page_content(client: &reqwest::Client, url: &str) -> Result<Page, String> {
+        let mut response = client.get(url)
+            .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,application/pdf;q=0.8,*/*;q=0.7")
             .send()
             .await
             .map_err(|e| format!("Network error while fetching page: {}", e))?;
@@ -674,14 +1428,465 @@ impl McpServer {
             return Err(format!("❌ Failed to fetch page: HTTP {}\n\nThere may be a temporary issue with the website.", response.status()));
         }
 
-        let html = response.text().await
-            .map_err(|e| format!("Error reading page response: {}", e))?;
+        let content_type = response.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
 
-        // Convert HTML to readable text
-        let text_content = from_read(html.as_bytes(), 80)
-            .map_err(|e| format!("Error converting HTML to text: {}", e))?;
-        
-        Ok(text_content)
+        // Declared binaries are rejected without downloading them
+        if content::formats::is_declared_binary(content_type.as_deref()) {
+            let mime = content::formats::essence(content_type.as_deref().unwrap_or_default());
+            return Err(ContentError::Binary { mime, size: response.content_length().map(|size| size as usize) }.to_string());
+        }
+
+        // Declared PDFs are capped before the download; anything that is or
+        // starts like a PDF is capped while it streams in.
+        let declared_pdf = content::pdf::is_declared(content_type.as_deref(), url);
+        let limit = content::pdf::max_bytes();
+        if let Some(size) = response.content_length().filter(|size| declared_pdf && *size as usize > limit) {
+            return Err(PdfError::TooLarge { size: size as usize, limit }.to_string());
+        }
+
+        // Everything else is checked for binary content once the first
+        // bytes are in, and capped at the page limit.
+        let page_limit = content::formats::max_bytes();
+        let declared_size = response.content_length().map(|size| size as usize);
+        let mut body = Vec::new();
+        let mut sniffed = false;
+        while let Some(chunk) = response.chunk().await.map_err(|e| format!("Error reading page response: {}", e))? {
+            body.extend_from_slice(&chunk);
+            let is_pdf = declared_pdf || content::pdf::has_signature(&body);
+            if !sniffed && body.len() >= content::formats::SNIFF_BYTES {
+                sniffed = true;
+                content::formats::check_head(content_type.as_deref(), &body, declared_size).map_err(|e| e.to_string())?;
+            }
+            if body.len() > limit && is_pdf {
+                return Err(PdfError::TooLarge { size: body.len(), limit }.to_string());
+            }
+            if body.len() > page_limit && !is_pdf {
+                return Err(ContentError::TooLarge { limit: page_limit }.to_string());
+            }
+        }
+
+        let kind = content::formats::detect(content_type.as_deref(), url, &body).map_err(|e| e.to_string())?;
+        debug!("Detected {:?} for {} (Content-Type: {:?})", kind, url, content_type);
+
+        match kind {
+            DocumentKind::Pdf => {
+                info!("📑 Extracting text from PDF ({} bytes)", body.len());
+                let pdf = tokio::task::spawn_blocking(move || content::pdf::extract(&body))
+                    .await
+                    .map_err(|_| PdfError::Parse("the parser crashed on this file".to_string()).to_string())?
+                    .map_err(|e| e.to_string())?;
+                Ok(Page { source: Source::Pdf { text: pdf.text, pages: pdf.pages }, ..Default::default() })
+            }
+            DocumentKind::Html | DocumentKind::Text(_) => {
+                let (text, charset) = content::encoding::decode(&body, content_type.as_deref(), url);
+                debug!("Decoded {} as {} ({})", url, charset.name(), charset.source.as_str());
+                let page = match kind {
+                    DocumentKind::Text(kind) => Page {
+                        source: Source::Text { kind, text },
+                        charset: Some(charset),
+                        ..Default::default()
+                    },
+                    _ => Page {
+                        metadata: Some(content::metadata::extract(&text, url)),
+                        source: Source::Html(text),
+                        charset: Some(charset),
+                        ..Default::default()
+                    },
+                };
+                Ok(page)
+            }
+        }
+    }
+
+    /// The page named by the `url` or `cursor` argument, or the current page
+    /// when neither is given. Pages not in the session yet are fetched,
+    /// rendered with the default options and stored, without changing the
+    /// current page.
+    async fn load_page(client: &reqwest::Client, arguments: &Value, session_id: &str) -> Result<(String, Page), String> {
+        let url_arg = arguments.get("url").and_then(|v| v.as_str()).filter(|u| !u.trim().is_empty());
+        let cursor_arg = arguments.get("cursor").filter(|v| !v.is_null());
+
+        let cached = {
+            let sessions = SESSIONS.read().map_err(|e| format!("Session lock error: {}", e))?;
+            let session = sessions.get(session_id);
+            let url = match (url_arg, cursor_arg) {
+                (Some(url), _) => url.to_string(),
+                (None, Some(cursor)) => cursor.as_u64()
+                    .and_then(|c| session?.cursors.get(c as usize).cloned())
+                    .ok_or_else(|| format!("❌ Unknown cursor: {}. Use a cursor returned by open.", cursor))?,
+                (None, None) => session
+                    .and_then(|s| s.current_url.clone())
+                    .ok_or("❌ No page is currently open.\nPlease open a page first using the 'open' tool, or pass a url.")?,
+            };
+            let page = session.and_then(|s| s.pages.get(&url)).cloned();
+            (url, page)
+        };
+
+        match cached {
+            (url, Some(page)) => Ok((url, page)),
+            (url, None) => {
+                let (page, _) = Self::fetch_into_session(client, &url, session_id).await?;
+                Ok((url, page))
+            }
+        }
+    }
+
+    /// The session's copy of `url` as last rendered, so line numbers stay
+    /// those of earlier responses, or the page fetched and stored with the
+    /// default rendering. Returns the page and its cursor.
+    async fn session_page(client: &reqwest::Client, url: &str, session_id: &str) -> Result<(Page, usize), String> {
+        let cached = {
+            let sessions = SESSIONS.read().map_err(|e| format!("Session lock error: {}", e))?;
+            sessions.get(session_id)
+                .and_then(|session| Some((session.pages.get(url)?.clone(), session.cursor_of(url)?)))
+        };
+        match cached {
+            Some(found) => Ok(found),
+            None => Self::fetch_into_session(client, url, session_id).await,
+        }
+    }
+
+    async fn fetch_into_session(client: &reqwest::Client, url: &str, session_id: &str) -> Result<(Page, usize), String> {
+        let mut page = Self::fetch_page_content(client, url).await?;
+        page.rendered = content::render(&page.source, &RenderOptions::default(), url)?;
+        page.options = RenderOptions::default();
+
+        let mut sessions = SESSIONS.write().map_err(|e| format!("Session lock error: {}", e))?;
+        let session = sessions.entry(session_id.to_string()).or_default();
+        let cursor = session.insert_page(url, page.clone());
+        Ok((page, cursor))
+    }
+
+    async fn execute_extract_tables(client: &reqwest::Client, arguments: &Value, session_id: &str) -> Result<ToolOutput, String> {
+        let index = arguments.get("index").and_then(|v| v.as_u64()).map(|i| i as usize);
+        let max_rows = arguments.get("max_rows")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_TABLE_ROWS as u64) as usize;
+        let csv = match arguments.get("format").and_then(|v| v.as_str()).unwrap_or("markdown") {
+            "markdown" | "md" => false,
+            "csv" => true,
+            other => return Err(format!("❌ Invalid format: '{}'. Use \"markdown\" or \"csv\".", other)),
+        };
+
+        let (url, page) = Self::load_page(client, arguments, session_id).await?;
+        let Source::Html(html) = &page.source else {
+            return Err(format!("❌ {} is not an HTML page, so it has no tables.", url));
+        };
+
+        info!("📊 Extracting tables from {}", url);
+        let mut tables = content::tables::extract(html);
+
+        if tables.is_empty() {
+            return Ok(ToolOutput::from(format!("📊 No tables found in {}", url)));
+        }
+
+        let total = tables.len();
+        if let Some(index) = index {
+            let available: Vec<String> = tables.iter().map(|t| t.index.to_string()).collect();
+            tables.retain(|t| t.index == index);
+            if tables.is_empty() {
+                return Err(format!(
+                    "❌ No table with index {} in {}.\nAvailable table indexes: {}",
+                    index, url, available.join(", ")
+                ));
+            }
+        }
+
+        let mut result = format!("📊 **Found {} table(s) in {}**\n", total, url);
+        for table in &tables {
+            result.push_str(&format!("\n**Table {}**", table.index));
+            if let Some(caption) = &table.caption {
+                result.push_str(&format!(": {}", caption));
+            }
+            result.push_str(&format!(" ({} rows × {} columns)\n", table.rows.len(), table.columns()));
+            if table.truncated {
+                result.push_str(&format!("⚠️ Table exceeds {} cells; the rows past that were dropped.\n", content::tables::MAX_CELLS));
+            }
+
+            if csv {
+                result.push_str(&format!("```csv\n{}\n```\n", table.to_csv(max_rows)));
+            } else {
+                result.push_str(&format!("{}\n", table.to_markdown(max_rows)));
+            }
+            if table.rows.len() > max_rows {
+                result.push_str(&format!("... {} more rows (raise max_rows to see them)\n", table.rows.len() - max_rows));
+            }
+        }
+        if index.is_none() && total > 1 {
+            result.push_str("\n💡 Pass index to extract a single table.");
+        }
+
+        let structured_tables: Vec<Value> = tables.iter()
+            .map(|table| {
+                json!({
+                    "index": table.index,
+                    "caption": table.caption,
+                    "headers": table.headers,
+                    "rows": table.rows.iter().take(max_rows).collect::<Vec<_>>(),
+                    "total_rows": table.rows.len(),
+                    "columns": table.columns(),
+                    "truncated": table.truncated,
+                })
+            })
+            .collect();
+
+        Ok(ToolOutput {
+            text: result,
+            structured: Some(json!({"url": url, "total_tables": total, "tables": structured_tables})),
+        })
+    }
+
+    async fn execute_select(client: &reqwest::Client, arguments: &Value, session_id: &str) -> Result<ToolOutput, String> {
+        let css = arguments.get("selector")
+            .and_then(|v| v.as_str())
+            .ok_or("Missing required parameter: selector")?;
+
+        if css.trim().is_empty() {
+            return Err("❌ Error: Selector cannot be empty.".to_string());
+        }
+
+        let max_results = arguments.get("max_results")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_SELECT_RESULTS as u64)
+            .max(1) as usize;
+        let max_html_chars = arguments.get("max_html_chars")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_SELECT_HTML_CHARS as u64) as usize;
+
+        let (url, page) = Self::load_page(client, arguments, session_id).await?;
+        let Source::Html(html) = &page.source else {
+            return Err(format!("❌ {} is not an HTML page, so CSS selectors do not apply.", url));
+        };
+
+        info!("🎯 Selecting '{}' in {}", css, url);
+        let (matches, total) = content::select::select(html, css, max_results, max_html_chars)?;
+
+        if total == 0 {
+            return Ok(ToolOutput::from(format!("🎯 No elements match '{}' in {}", css, url)));
+        }
+
+        let mut result = format!("🎯 **{} element(s) match '{}' in {}:**\n", total, css, url);
+        for (i, element) in matches.iter().enumerate() {
+            result.push_str(&format!("\n**{}. <{}>**", i + 1, element.tag));
+            let attributes: Vec<String> = element.attributes.iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect();
+            if !attributes.is_empty() {
+                result.push_str(&format!(" {}", attributes.join(" ")));
+            }
+            result.push('\n');
+            if !element.text.is_empty() {
+                result.push_str(&format!("Text: {}{}\n", element.text, if element.text_truncated { "…" } else { "" }));
+            }
+            result.push_str(&format!("```html\n{}{}\n```\n", element.html, if element.html_truncated { "…" } else { "" }));
+        }
+
+        if total > matches.len() {
+            result.push_str(&format!("\n... and {} more (showing first {})\n", total - matches.len(), matches.len()));
+        }
+
+        Ok(ToolOutput {
+            text: result,
+            structured: Some(json!({"url": url, "selector": css, "total": total, "matches": matches})),
+        })
+    }
+
+    async fn execute_retrieve(client: &reqwest::Client, arguments: &Value, session_id: &str) -> Result<ToolOutput, String> {
+        let query = arguments.get("query")
+            .and_then(|v| v.as_str())
+            .ok_or("Missing required parameter: query")?;
+
+        let terms = rank::tokenize(query);
+        if terms.is_empty() {
+            return Err(FindError::NoSearchTerms.to_string());
+        }
+
+        let top_k = arguments.get("top_k")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).clamp(1, MAX_RETRIEVE_PASSAGES))
+            .unwrap_or(DEFAULT_RETRIEVE_PASSAGES);
+
+        let (url, page) = Self::load_page(client, arguments, session_id).await?;
+        let cursor = {
+            let sessions = SESSIONS.read().map_err(|e| format!("Session lock error: {}", e))?;
+            sessions.get(session_id).and_then(|s| s.cursor_of(&url))
+        };
+
+        info!("🎯 Retrieving passages for '{}' in {}", query, url);
+        let content = page.rendered.text;
+        let passages = retrieve::retrieve(&content, &terms, top_k);
+
+        if passages.is_empty() {
+            return Ok(ToolOutput::from(format!(
+                "🎯 No passages in {} match '{}'.\n\n💡 Try other words, or `find` with `fuzzy: true` for shorter matches.",
+                url, query
+            )));
+        }
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut result = format!("🎯 **Top {} passage(s) for '{}' in {}", passages.len(), query, url);
+        if let Some(cursor) = cursor {
+            result.push_str(&format!(" (cursor {})", cursor));
+        }
+        result.push_str(":**\n");
+
+        let mut structured = Vec::new();
+        for (i, passage) in passages.iter().enumerate() {
+            let chunk = &passage.chunk;
+            let range = Span { start: chunk.start, end: chunk.end }.label();
+            result.push_str(&format!("\n**{}. {}** (score {:.2}; matched: {})", i + 1, range, passage.score, passage.terms.join(", ")));
+            if let Some(heading) = &chunk.heading {
+                result.push_str(&format!(" § {}", heading));
+            }
+            result.push('\n');
+            for (line_num, line) in lines.iter().enumerate().take(chunk.end + 1).skip(chunk.start) {
+                result.push_str(&format!("L{}: {}\n", line_num, line));
+            }
+
+            structured.push(json!({
+                "rank": i + 1,
+                "start": chunk.start,
+                "end": chunk.end,
+                "heading": chunk.heading,
+                "score": passage.score,
+                "matched": passage.terms,
+                "text": lines[chunk.start..=chunk.end].join("\n"),
+            }));
+        }
+
+        result.push_str("\n💡 Cite passages by their line ranges, or `open` with `loc` to read around them.");
+
+        Ok(ToolOutput {
+            text: result,
+            structured: Some(json!({"url": url, "cursor": cursor, "query": query, "passages": structured})),
+        })
+    }
+
+    async fn execute_research(client: &reqwest::Client, arguments: &Value, session_id: &str) -> Result<ToolOutput, String> {
+        let query = arguments.get("query")
+            .and_then(|v| v.as_str())
+            .ok_or("Missing required parameter: query")?;
+
+        if query.trim().is_empty() {
+            return Err("❌ Error: Search query cannot be empty.\n\nPlease provide a search term.".to_string());
+        }
+        let terms = rank::tokenize(query);
+        if terms.is_empty() {
+            return Err(FindError::NoSearchTerms.to_string());
+        }
+
+        let num_results = arguments.get("num_results")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).clamp(1, MAX_OPEN_MANY_URLS))
+            .unwrap_or(DEFAULT_RESEARCH_RESULTS);
+        let passages_per_result = arguments.get("passages")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).clamp(1, MAX_RESEARCH_PASSAGES))
+            .unwrap_or(DEFAULT_RESEARCH_PASSAGES);
+        let filters = Self::parse_search_filters(arguments)?;
+
+        let backend = search::backend_from_env().map_err(|e| e.to_string())?;
+
+        info!("🔬 Researching '{}' via {}: reading top {} result(s)", query, backend.name(), num_results);
+
+        let search_query = SearchQuery {
+            query: query.to_string(),
+            limit: num_results,
+            offset: 0,
+            filters,
+            vertical: Vertical::Web,
+        };
+        let page = backend.search(client, &search_query).await
+            .map_err(|e| e.to_string())?;
+        let results = page.results;
+
+        if results.is_empty() {
+            return Ok(ToolOutput::from(format!("🔬 No results found for query: \"{}\"\n\n💡 **Suggestions:**\n- Try different search terms\n- Check spelling\n- Use more general terms", query)));
+        }
+
+        // Pages already in the session keep their rendering, so citations
+        // given for them earlier stay valid
+        let urls: Vec<String> = results.iter().map(|r| r.url.clone()).collect();
+        let outcomes: Vec<Result<(Page, usize), String>> = stream::iter(urls)
+            .map(|url| async move { Self::session_page(client, &url, session_id).await })
+            .buffered(default_open_concurrency())
+            .collect()
+            .await;
+
+        let read = outcomes.iter().filter(|o| o.is_ok()).count();
+        let mut result = format!("🔬 **Research: \"{}\"** (read {} of {} top result(s))\n", query, read, results.len());
+        let mut structured = Vec::new();
+
+        for (index, (search_result, outcome)) in results.iter().zip(&outcomes).enumerate() {
+            result.push_str(&format!("\n## {}. {}\n🔗 {}\n", index + 1, search_result.title, search_result.url));
+            Self::push_result_details(&mut result, search_result);
+
+            let (page, cursor) = match outcome {
+                Ok(opened) => opened,
+                Err(e) => {
+                    result.push_str(&format!("❌ Could not read this result: {}\n", e));
+                    if !search_result.snippet.is_empty() {
+                        result.push_str(&format!("Snippet: {}\n", search_result.snippet));
+                    }
+                    structured.push(json!({
+                        "rank": index + 1,
+                        "title": search_result.title,
+                        "url": search_result.url,
+                        "error": e,
+                    }));
+                    continue;
+                }
+            };
+
+            let content = &page.rendered.text;
+            let lines: Vec<&str> = content.lines().collect();
+            let passages = retrieve::retrieve(content, &terms, passages_per_result);
+            result.push_str(&format!("📄 cursor {}, {} lines\n", cursor, lines.len()));
+            if passages.is_empty() {
+                result.push_str(&format!("💡 No passage matched the query; open cursor {} to read the page.\n", cursor));
+            }
+
+            let mut cited = Vec::new();
+            for passage in &passages {
+                let chunk = &passage.chunk;
+                let citation = format!("【{}†{}】", cursor, Span { start: chunk.start, end: chunk.end }.label());
+                result.push_str(&format!("\n**{}** (score {:.2})", citation, passage.score));
+                if let Some(heading) = &chunk.heading {
+                    result.push_str(&format!(" § {}", heading));
+                }
+                result.push('\n');
+                for (line_num, line) in lines.iter().enumerate().take(chunk.end + 1).skip(chunk.start) {
+                    result.push_str(&format!("L{}: {}\n", line_num, line));
+                }
+                cited.push(json!({
+                    "citation": citation,
+                    "start": chunk.start,
+                    "end": chunk.end,
+                    "heading": chunk.heading,
+                    "score": passage.score,
+                    "text": lines[chunk.start..=chunk.end].join("\n"),
+                }));
+            }
+
+            structured.push(json!({
+                "rank": index + 1,
+                "title": search_result.title,
+                "url": search_result.url,
+                "cursor": cursor,
+                "total_lines": lines.len(),
+                "passages": cited,
+            }));
+        }
+
+        result.push_str("\n💡 Cite passages as 【cursor†Lstart-Lend】, or `open` a result with `loc` to read around a passage.");
+
+        Ok(ToolOutput {
+            text: result,
+            structured: Some(json!({"query": query, "results": structured})),
+        })
     }
 
     async fn execute_find(arguments: &Value, session_id: &str) -> Result<String, String> {
@@ -693,83 +1898,257 @@ impl McpServer {
             return Err("❌ Error: Search pattern cannot be empty.".to_string());
         }
 
+        let flag = |name: &str| arguments.get(name).and_then(|v| v.as_bool()).unwrap_or(false);
+        let options = FindOptions {
+            regex: flag("regex"),
+            case_sensitive: flag("case_sensitive"),
+            whole_word: flag("whole_word"),
+        };
+        let fuzzy = flag("fuzzy");
+        let context = arguments.get("context_lines")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).min(MAX_FIND_CONTEXT_LINES))
+            .unwrap_or(DEFAULT_FIND_CONTEXT_LINES);
+        let session_scope = match arguments.get("scope").and_then(|v| v.as_str()).unwrap_or("page") {
+            "page" => false,
+            "session" => true,
+            other => return Err(format!("❌ Invalid scope: '{}'. Use \"page\" or \"session\".", other)),
+        };
+        let max_matches = arguments.get("max_matches")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).clamp(1, MAX_FIND_MATCHES))
+            .unwrap_or(if session_scope { DEFAULT_SESSION_FIND_MATCHES } else { DEFAULT_FIND_MATCHES });
+
+        // `None` selects fuzzy ranking
+        let regex = if fuzzy {
+            if options.regex {
+                return Err("❌ Error: `fuzzy` cannot be combined with `regex`.".to_string());
+            }
+            if rank::tokenize(pattern).is_empty() {
+                return Err(FindError::NoSearchTerms.to_string());
+            }
+            None
+        } else {
+            Some(find::compile(pattern, &options).map_err(|e| e.to_string())?)
+        };
+
+        info!("🔎 Finding pattern '{}' ({:?}, fuzzy: {}, session scope: {})", pattern, options, fuzzy, session_scope);
+
+        if session_scope {
+            return Self::find_in_session(session_id, pattern, regex, context, max_matches).await;
+        }
+
         // Get the content to search in
-        let (content, url) = if let Some(url_arg) = arguments.get("url").and_then(|v| v.as_str()) {
-            // Search in specific URL
+        let (content, url) = {
             let sessions = SESSIONS.read().map_err(|e| format!("Session lock error: {}", e))?;
-            if let Some(session) = sessions.get(session_id) {
-                if let Some(page_content) = session.pages.get(url_arg) {
-                    (page_content.clone(), url_arg.to_string())
-                } else {
-                    return Err(format!("❌ Page not found in session: {}\nPlease open the page first.", url_arg));
-                }
-            } else {
-                return Err("❌ No active session found.".to_string());
+            let session = sessions.get(session_id).ok_or("❌ No active session found.")?;
+            let url_arg = arguments.get("url").and_then(|v| v.as_str());
+            let cursor_arg = arguments.get("cursor").filter(|v| !v.is_null());
+
+            match (url_arg, cursor_arg) {
+                (Some(url_arg), _) => match session.pages.get(url_arg) {
+                    Some(page) => (page.rendered.text.clone(), url_arg.to_string()),
+                    None => return Err(format!("❌ Page not found in session: {}\nPlease open the page first.", url_arg)),
+                },
+                (None, Some(cursor)) => cursor.as_u64()
+                    .and_then(|c| session.cursors.get(c as usize))
+                    .and_then(|url| Some((session.pages.get(url)?.rendered.text.clone(), url.clone())))
+                    .ok_or_else(|| format!("❌ Unknown cursor: {}. Use a cursor returned by open.", cursor))?,
+                (None, None) => match (&session.current_content, &session.current_url) {
+                    (Some(content), Some(url)) => (content.clone(), url.clone()),
+                    _ => return Err("❌ No page is currently open.\nPlease open a page first using the 'open' tool.".to_string()),
+                },
             }
+        };
+
+        let hits = Self::find_hits(&content, pattern, regex.as_ref())?;
+        if hits.is_empty() {
+            return Ok(format!("🔎 No matches found for pattern: '{}'\n\n💡 **Suggestions:**\n- Check spelling\n- Try a different search term\n- Use partial words or phrases", pattern));
+        }
+
+        let total = hits.len();
+        let offset = arguments.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        if offset >= total {
+            return Err(format!("❌ Error: offset {} is past the last match ({} found).", offset, total));
+        }
+        let shown = &hits[offset..(offset + max_matches).min(total)];
+
+        let mut result = if fuzzy {
+            format!("🎯 **Found {} relevant passage(s) for '{}' in {}, best first", total, pattern, url)
         } else {
-            // Search in current page
-            let sessions = SESSIONS.read().map_err(|e| format!("Session lock error: {}", e))?;
-            if let Some(session) = sessions.get(session_id) {
-                if let (Some(content), Some(url)) = (&session.current_content, &session.current_url) {
-                    (content.clone(), url.clone())
+            format!("🔎 **Found {} match(es) for '{}' in {}", total, pattern, url)
+        };
+        if shown.len() < total {
+            result.push_str(&format!(" (showing {}-{})", offset + 1, offset + shown.len()));
+        }
+        result.push_str(":**\n\n");
+
+        Self::push_find_context(&mut result, &content, shown, offset, context, fuzzy);
+
+        let next = offset + shown.len();
+        if next < total {
+            result.push_str(&format!(
+                "... {} more {}. Call `find` again with `offset: {}` to continue.\n\n",
+                total - next, if fuzzy { "passage(s)" } else { "match(es)" }, next,
+            ));
+        }
+
+        result.push_str("💡 Use the line numbers to navigate to specific matches.");
+
+        Ok(result)
+    }
+
+    /// Matched line spans of `content`, each with a note for its heading.
+    /// Without a regex, passages are ranked against `pattern` instead.
+    fn find_hits(content: &str, pattern: &str, regex: Option<&Regex>) -> Result<Vec<FindHit>, String> {
+        match regex {
+            Some(regex) => Ok(find::find_spans(content, regex)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|span| (span, None))
+                .collect()),
+            None => Ok(find::ranked_passages(content, pattern)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|p| (p.span, Some(format!("score {:.2}; matched: {}", p.score, p.terms.join(", ")))))
+                .collect()),
+        }
+    }
+
+    /// Append `shown` with their context, merging overlapping windows.
+    /// Hits are numbered from `offset + 1`.
+    fn push_find_context(result: &mut String, content: &str, shown: &[FindHit], offset: usize, context: usize, fuzzy: bool) {
+        let noun = if fuzzy { "Passage" } else { "Match" };
+        let lines: Vec<&str> = content.lines().collect();
+        let spans: Vec<Span> = shown.iter().map(|(span, _)| *span).collect();
+
+        for window in find::context_windows(&spans, context, lines.len()) {
+            for &member in &window.members {
+                let (span, note) = &shown[member];
+                let number = offset + member + 1;
+                match note {
+                    Some(note) => result.push_str(&format!("**{} {} at {} ({}):**\n", noun, number, span.label(), note)),
+                    None => result.push_str(&format!("**{} {} at {}:**\n", noun, number, span.label())),
+                }
+            }
+            for (line_num, line) in lines.iter().enumerate().take(window.end + 1).skip(window.start) {
+                if spans.iter().any(|span| (span.start..=span.end).contains(&line_num)) {
+                    result.push_str(&format!("L{}: >>> {} <<<\n", line_num, line));
                 } else {
-                    return Err("❌ No page is currently open.\nPlease open a page first using the 'open' tool.".to_string());
+                    result.push_str(&format!("L{}: {}\n", line_num, line));
                 }
-            } else {
-                return Err("❌ No active session found.".to_string());
             }
+            result.push('\n');
+        }
+    }
+
+    /// `find` over every page opened in the session. Pages are searched in
+    /// parallel and listed by hit density (matches per 100 lines), each with
+    /// its first `max_matches` hits.
+    async fn find_in_session(
+        session_id: &str,
+        pattern: &str,
+        regex: Option<Regex>,
+        context: usize,
+        max_matches: usize,
+    ) -> Result<String, String> {
+        let pages: Vec<(usize, String, Option<String>, String)> = {
+            let sessions = SESSIONS.read().map_err(|e| format!("Session lock error: {}", e))?;
+            let session = sessions.get(session_id).ok_or("❌ No active session found.")?;
+            session.cursors.iter()
+                .enumerate()
+                .filter_map(|(cursor, url)| {
+                    let page = session.pages.get(url)?;
+                    Some((cursor, url.clone(), page.rendered.title.clone(), page.rendered.text.clone()))
+                })
+                .collect()
         };
+        if pages.is_empty() {
+            return Err("❌ No pages have been opened in this session.\nPlease open a page first using the 'open' tool.".to_string());
+        }
+        let page_count = pages.len();
+
+        let searches = pages.into_iter().map(|(cursor, url, title, text)| {
+            let regex = regex.clone();
+            let pattern = pattern.to_string();
+            let search = tokio::task::spawn_blocking(move || {
+                let hits = Self::find_hits(&text, &pattern, regex.as_ref());
+                (title, text, hits)
+            });
+            // A search that dies takes only its own page with it
+            async move {
+                match search.await {
+                    Ok((title, text, hits)) => SessionFind { cursor, url, title, text, hits },
+                    Err(e) => SessionFind { cursor, url, title: None, text: String::new(), hits: Err(format!("search task failed: {}", e)) },
+                }
+            }
+        });
+        let outcomes = join_all(searches).await;
 
-        info!("🔎 Finding pattern '{}' in {}", pattern, url);
+        Ok(Self::format_session_find(pattern, outcomes, page_count, regex.is_none(), context, max_matches))
+    }
 
-        // Search for pattern in content
-        let lines: Vec<&str> = content.lines().collect();
-        let pattern_lower = pattern.to_lowercase();
-        let mut matches = Vec::new();
-
-        for (line_num, line) in lines.iter().enumerate() {
-            if line.to_lowercase().contains(&pattern_lower) {
-                // Show context around the match
-                let start_line = line_num.saturating_sub(2);
-                let end_line = (line_num + 3).min(lines.len());
-                let context_lines: Vec<String> = lines[start_line..end_line]
-                    .iter()
-                    .enumerate()
-                    .map(|(i, line)| {
-                        let actual_line_num = start_line + i;
-                        if actual_line_num == line_num {
-                            format!("L{}: >>> {} <<<", actual_line_num, line)
-                        } else {
-                            format!("L{}: {}", actual_line_num, line)
-                        }
-                    })
-                    .collect();
-                
-                matches.push((line_num, context_lines));
+    /// Rank the pages of a session-wide `find` by hit density and lay them
+    /// out, listing pages without matches and pages whose search failed
+    /// after the results.
+    fn format_session_find(
+        pattern: &str,
+        outcomes: Vec<SessionFind>,
+        page_count: usize,
+        fuzzy: bool,
+        context: usize,
+        max_matches: usize,
+    ) -> String {
+        let mut found = Vec::new();
+        let mut misses = Vec::new();
+        let mut failures = Vec::new();
+        for SessionFind { cursor, url, title, text, hits } in outcomes {
+            match hits {
+                Ok(hits) if hits.is_empty() => misses.push(format!("cursor {} ({})", cursor, url)),
+                Ok(hits) => {
+                    let density = hits.len() as f64 * 100.0 / text.lines().count().max(1) as f64;
+                    found.push((cursor, url, title, text, hits, density));
+                }
+                Err(e) => failures.push(format!("cursor {} ({}): {}", cursor, url, e)),
             }
         }
 
-        if matches.is_empty() {
-            return Ok(format!("🔎 No matches found for pattern: '{}'\n\n💡 **Suggestions:**\n- Check spelling\n- Try a different search term\n- Use partial words or phrases", pattern));
+        if found.is_empty() && failures.is_empty() {
+            return format!("🔎 No matches found for pattern: '{}' in any of the {} open page(s).", pattern, page_count);
         }
 
-        let mut result = format!("🔎 **Found {} match(es) for '{}' in {}:**\n\n", matches.len(), pattern, url);
+        found.sort_by(|a, b| b.5.total_cmp(&a.5).then(b.4.len().cmp(&a.4.len())).then(a.0.cmp(&b.0)));
+
+        let mut result = format!(
+            "🔎 **'{}' found in {} of {} open page(s), ranked by hit density:**\n\n",
+            pattern, found.len(), page_count,
+        );
+        for (cursor, url, title, text, hits, density) in &found {
+            result.push_str(&format!("## [cursor {}] {}\n", cursor, title.as_deref().unwrap_or(url)));
+            result.push_str(&format!("🔗 {}\n", url));
+            result.push_str(&format!(
+                "📊 {} {} ({:.1} per 100 lines)\n\n",
+                hits.len(), if fuzzy { "relevant passage(s)" } else { "match(es)" }, density,
+            ));
 
-        for (i, (line_num, context)) in matches.iter().enumerate().take(10) {
-            result.push_str(&format!("**Match {} at line {}:**\n", i + 1, line_num));
-            for context_line in context {
-                result.push_str(&format!("{}\n", context_line));
+            let shown = &hits[..hits.len().min(max_matches)];
+            Self::push_find_context(&mut result, text, shown, 0, context, fuzzy);
+            if hits.len() > shown.len() {
+                result.push_str(&format!(
+                    "... {} more. Call `find` with `cursor: {}` and `offset: {}` to see them.\n\n",
+                    hits.len() - shown.len(), cursor, shown.len(),
+                ));
             }
-            result.push_str("\n");
         }
 
-        if matches.len() > 10 {
-            result.push_str(&format!("... and {} more matches (showing first 10)\n\n", matches.len() - 10));
+        if !misses.is_empty() {
+            result.push_str(&format!("📭 No matches in: {}\n", misses.join(", ")));
+        }
+        for failure in &failures {
+            result.push_str(&format!("⚠️ Search failed for {}\n", failure));
         }
 
-        result.push_str("💡 Use the line numbers to navigate to specific matches.");
-
-        Ok(result)
+        result.trim_end().to_string()
     }
 
     fn create_success_response(id: Option<Value>, result: Value) -> JsonRpcResponse {
@@ -809,4 +2188,258 @@ impl McpServer {
             "message": "This endpoint is not available. Use POST /mcp for MCP requests."
         })))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::mock;
+    use axum::routing::get;
+
+    #[test]
+    fn search_offset_prefers_cursor_then_offset_then_page() {
+        let offset = |arguments: Value| McpServer::search_offset(&arguments, 10, "brave");
+
+        assert_eq!(offset(json!({})), Ok(0));
+        assert_eq!(offset(json!({"page": 3})), Ok(20));
+        assert_eq!(offset(json!({"page": 0})), Ok(0));
+        assert_eq!(offset(json!({"page": 3, "offset": 7})), Ok(7));
+        assert_eq!(offset(json!({"page": 3, "offset": 7, "cursor": "brave:40"})), Ok(40));
+        assert!(offset(json!({"cursor": "brave:next"})).is_err());
+        assert!(offset(json!({"cursor": "exa:40"})).is_err());
+    }
+
+    #[tokio::test]
+    async fn pdf_size_cap_applies_to_sniffed_pdfs() {
+        let limit = content::pdf::max_bytes();
+        let base = mock::serve(axum::Router::new().route("/report", get(move || async move {
+            let mut body = b"%PDF-1.7\n".to_vec();
+            body.resize(limit + (1 << 20), b' ');
+            ([("Content-Type", "text/plain")], body)
+        }))).await;
+
+        let error = McpServer::fetch_page_content(&reqwest::Client::new(), &format!("{}/report", base)).await.unwrap_err();
+
+        // The download stops soon after the limit instead of reading it all
+        let size: usize = error.strip_prefix("❌ PDF is too large: ")
+            .and_then(|rest| rest.split(' ').next())
+            .and_then(|size| size.parse().ok())
+            .unwrap_or_else(|| panic!("unexpected error: {}", error));
+        assert!(size > limit && size < limit + (1 << 20));
+    }
+
+    #[tokio::test]
+    async fn undeclared_binaries_are_rejected_from_their_first_bytes() {
+        // The body never ends, so only an early check can answer
+        let base = mock::serve(axum::Router::new().route("/download", get(|| async {
+            let mut head = b"\x89PNG\r\n\x1a\n".to_vec();
+            head.resize(content::formats::SNIFF_BYTES, 0);
+            let stream = stream::once(async move { Ok::<_, std::io::Error>(head) }).chain(stream::pending());
+            ([("Content-Type", "application/octet-stream")], axum::body::Body::from_stream(stream))
+        }))).await;
+
+        let (client, url) = (reqwest::Client::new(), format!("{}/download", base));
+        let fetch = McpServer::fetch_page_content(&client, &url);
+        let error = tokio::time::timeout(std::time::Duration::from_secs(5), fetch).await
+            .expect("the download should stop after the first bytes")
+            .unwrap_err();
+        assert!(error.contains("image/png"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn text_bodies_are_capped_while_downloading() {
+        let limit = content::formats::max_bytes();
+        let base = mock::serve(axum::Router::new().route("/huge.txt", get(move || async move {
+            ([("Content-Type", "text/plain")], "a".repeat(limit + 1))
+        }))).await;
+
+        let error = McpServer::fetch_page_content(&reqwest::Client::new(), &format!("{}/huge.txt", base)).await.unwrap_err();
+        assert_eq!(error, ContentError::TooLarge { limit }.to_string());
+    }
+
+    #[tokio::test]
+    async fn metadata_counts_against_the_token_budget() {
+        let base = mock::serve(axum::Router::new().route("/faq", get(|| async {
+            let answer = "A long answer that goes on and on. ".repeat(30);
+            let questions: Vec<Value> = (0..10)
+                .map(|i| json!({"name": format!("Question {}?", i), "acceptedAnswer": {"text": answer}}))
+                .collect();
+            let ld = json!({"@type": "FAQPage", "mainEntity": questions});
+            axum::response::Html(format!(
+                "<html><head><script type=\"application/ld+json\">{}</script></head><body><p>Short page.</p></body></html>",
+                ld
+            ))
+        }))).await;
+
+        let arguments = json!({"url": format!("{}/faq", base), "max_tokens": 400});
+        let output = McpServer::execute_open(&reqwest::Client::new(), &arguments, "metadata-budget").await.unwrap();
+
+        let shown = output.structured.unwrap()["tokens"]["shown"].as_u64().unwrap();
+        assert!(shown <= 400, "{} tokens shown", shown);
+        assert!(output.text.contains("L0: Short page."));
+        assert!(output.text.contains("metadata line(s) left out"), "{}", output.text);
+    }
+
+    #[tokio::test]
+    async fn open_many_stays_within_the_shared_token_budget() {
+        let base = mock::serve(axum::Router::new()
+            .route("/a", get(|| async { ([("Content-Type", "text/plain")], "first page\nwith two lines") }))
+            .route("/b", get(|| async { ([("Content-Type", "text/plain")], "second page") })))
+            .await;
+        let one_line = tokens::count("L0: second page\n");
+
+        for budget in [1, one_line, one_line + 1] {
+            let arguments = json!({"urls": [format!("{}/a", base), format!("{}/b", base)], "max_tokens": budget});
+            let output = McpServer::execute_open_many(&reqwest::Client::new(), &arguments, "open-many-budget").await.unwrap();
+            let pages = output.structured.unwrap()["pages"].as_array().unwrap().clone();
+            let shown: u64 = pages.iter().map(|p| p["tokens"]["shown"].as_u64().unwrap()).sum();
+            assert!(shown as usize <= budget, "{} tokens shown for a budget of {}", shown, budget);
+        }
+    }
+
+    #[tokio::test]
+    async fn session_pages_keep_their_rendering() {
+        let base = mock::serve(axum::Router::new().route("/article", get(|| async {
+            axum::response::Html(format!("<html><body><p>{}</p></body></html>", "lorem ipsum ".repeat(40)))
+        }))).await;
+        let client = reqwest::Client::new();
+        let url = format!("{}/article", base);
+        let session_id = "session-page-rendering";
+
+        let unwrapped = RenderOptions { wrap: None, ..RenderOptions::default() };
+        let (opened, cursor) = McpServer::open_page(&client, &url, &unwrapped, session_id, false).await.unwrap();
+        assert_eq!(opened.rendered.text.lines().count(), 1);
+
+        let (page, same_cursor) = McpServer::session_page(&client, &url, session_id).await.unwrap();
+        assert_eq!((page.rendered.text, same_cursor), (opened.rendered.text.clone(), cursor));
+        let stored = SESSIONS.read().unwrap()[session_id].pages[&url].rendered.text.clone();
+        assert_eq!(stored, opened.rendered.text);
+    }
+
+    fn navigate(session: &mut BrowserSession, url: &str) {
+        session.visit(url);
+        session.current_url = Some(url.to_string());
+    }
+
+    #[test]
+    fn history_moves_back_and_forward() {
+        let mut session = BrowserSession::default();
+        for url in ["a", "b", "c", "d"] {
+            navigate(&mut session, url);
+        }
+        navigate(&mut session, "d");
+        assert_eq!(session.back, ["a", "b", "c"]);
+
+        assert_eq!(session.go_back(2).as_deref(), Some("b"));
+        session.current_url = Some("b".to_string());
+        assert_eq!(session.back, ["a"]);
+        assert_eq!(session.forward, ["d", "c"]);
+
+        assert_eq!(session.go_forward(1).as_deref(), Some("c"));
+        session.current_url = Some("c".to_string());
+        assert_eq!(session.back, ["a", "b"]);
+        assert_eq!(session.forward, ["d"]);
+
+        // Too far, or not at all: nothing moves
+        assert_eq!(session.go_back(3), None);
+        assert_eq!(session.go_forward(0), None);
+        assert_eq!((session.back.len(), session.forward.len()), (2, 1));
+
+        // A new page clears the forward stack
+        navigate(&mut session, "e");
+        assert_eq!(session.back, ["a", "b", "c"]);
+        assert!(session.forward.is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_history_moves_leave_the_stacks_alone() {
+        let session_id = "history-invalid-arguments";
+        {
+            let mut sessions = SESSIONS.write().unwrap();
+            let session = sessions.entry(session_id.to_string()).or_default();
+            for url in ["https://a.example/", "https://b.example/", "https://c.example/"] {
+                navigate(session, url);
+            }
+        }
+
+        let client = reqwest::Client::new();
+        let error = McpServer::execute_history(&client, &json!({"max_tokens": "bad"}), session_id, false).await.unwrap_err();
+        assert!(error.contains("Invalid max_tokens"), "{}", error);
+
+        let sessions = SESSIONS.read().unwrap();
+        let session = &sessions[session_id];
+        assert_eq!(session.back, ["https://a.example/", "https://b.example/"]);
+        assert!(session.forward.is_empty());
+        assert_eq!(session.current_url.as_deref(), Some("https://c.example/"));
+    }
+
+    #[test]
+    fn history_needs_a_current_page_and_is_capped() {
+        let mut session = BrowserSession::default();
+        assert_eq!(session.go_back(1), None);
+
+        for i in 0..=MAX_HISTORY + 5 {
+            navigate(&mut session, &i.to_string());
+        }
+        assert_eq!(session.back.len(), MAX_HISTORY);
+        assert_eq!(session.back.first().map(String::as_str), Some("5"));
+    }
+
+    fn seed_session(session_id: &str, pages: &[(&str, &str)]) {
+        let mut sessions = SESSIONS.write().unwrap();
+        let session = sessions.entry(session_id.to_string()).or_default();
+        for (url, text) in pages {
+            let rendered = RenderedPage { text: text.to_string(), ..Default::default() };
+            session.insert_page(url, Page { rendered, ..Default::default() });
+        }
+    }
+
+    #[tokio::test]
+    async fn session_find_ranks_pages_by_hit_density() {
+        let session_id = "session-find-ranking";
+        seed_session(session_id, &[
+            ("https://a.example/", "rust\none\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine"),
+            ("https://b.example/", "rust here\nrust there"),
+            ("https://c.example/", "nothing to see"),
+            ("https://d.example/", "rust\nrust\nrust\nrust"),
+        ]);
+        let regex = find::compile("rust", &FindOptions::default()).unwrap();
+
+        let output = McpServer::find_in_session(session_id, "rust", Some(regex), 0, 2).await.unwrap();
+
+        let position = |needle: &str| output.find(needle).unwrap_or_else(|| panic!("{} missing from {}", needle, output));
+        assert!(output.starts_with("🔎 **'rust' found in 3 of 4 open page(s)"));
+        // Equal density breaks ties on the number of hits
+        assert!(position("## [cursor 3]") < position("## [cursor 1]"));
+        assert!(position("## [cursor 1]") < position("## [cursor 0]"));
+        assert!(output.contains("... 2 more. Call `find` with `cursor: 3` and `offset: 2` to see them."));
+        assert!(!output.contains("## [cursor 2]"));
+        assert!(output.contains("📭 No matches in: cursor 2 (https://c.example/)"));
+    }
+
+    #[test]
+    fn session_find_reports_failed_pages_alongside_the_rest() {
+        let page = |cursor: usize, url: &str, hits: Result<Vec<FindHit>, String>| SessionFind {
+            cursor,
+            url: url.to_string(),
+            title: Some(format!("Page {}", cursor)),
+            text: "rust\nmore".to_string(),
+            hits,
+        };
+        let outcomes = vec![
+            page(0, "https://a.example/", Err("❌ Search timed out after 5s.".to_string())),
+            page(1, "https://b.example/", Ok(vec![(Span { start: 0, end: 0 }, None)])),
+        ];
+
+        let output = McpServer::format_session_find("rust", outcomes, 2, false, 0, 5);
+
+        assert!(output.contains("found in 1 of 2 open page(s)"));
+        assert!(output.contains("## [cursor 1] Page 1"));
+        assert!(output.ends_with("⚠️ Search failed for cursor 0 (https://a.example/): ❌ Search timed out after 5s."));
+
+        let only_failures = vec![page(0, "https://a.example/", Err("boom".to_string()))];
+        let output = McpServer::format_session_find("rust", only_failures, 1, false, 0, 5);
+        assert!(output.contains("found in 0 of 1 open page(s)") && output.contains("Search failed for cursor 0"));
+    }
+}
+
@@ -0,0 +1,29 @@
+//! In-process counters reported by the health endpoint. They live as long as
+//! the Lambda execution environment, so they describe one warm instance.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+lazy_static::lazy_static! {
+    static ref SEARCH_BLOCKED: Mutex<HashMap<&'static str, u64>> = Mutex::new(HashMap::new());
+}
+
+/// Count a search that a backend refused with a CAPTCHA, anomaly page or
+/// rate limit.
+pub fn record_search_blocked(backend: &'static str) {
+    if let Ok(mut counts) = SEARCH_BLOCKED.lock() {
+        *counts.entry(backend).or_insert(0) += 1;
+    }
+}
+
+pub fn snapshot() -> Value {
+    let blocked = SEARCH_BLOCKED.lock()
+        .map(|counts| counts.clone())
+        .unwrap_or_default();
+
+    json!({
+        "search_blocked_total": blocked.values().sum::<u64>(),
+        "search_blocked_by_backend": blocked,
+    })
+}
@@ -0,0 +1,129 @@
+//! Lexical relevance ranking: a word tokenizer with light stemming and an
+//! Okapi BM25 index over a set of passages. Runs in-process, no models or
+//! external services.
+
+use std::collections::{HashMap, HashSet};
+
+/// Term frequency saturation.
+const K1: f64 = 1.2;
+/// Passage length normalization.
+const B: f64 = 0.75;
+
+const STOPWORDS: &[&str] = &[
+    "a", "about", "an", "and", "are", "as", "at", "be", "by", "can", "do", "does", "for", "from", "has", "have",
+    "how", "in", "is", "it", "its", "of", "on", "or", "that", "the", "this", "to", "was", "were", "what", "when",
+    "where", "which", "who", "why", "will", "with",
+];
+
+/// Reduce a lowercase word to a rough stem so `tables`, `table` and
+/// `tabled` meet. Deliberately conservative: short words are left alone.
+fn stem(word: &str) -> String {
+    let len = word.chars().count();
+    let strip = |suffix: &str, min_len: usize| {
+        (len >= min_len && word.ends_with(suffix)).then(|| word[..word.len() - suffix.len()].to_string())
+    };
+    if let Some(base) = strip("ies", 5) {
+        return base + "y";
+    }
+    if let Some(base) = strip("ing", 6).or_else(|| strip("ed", 5)) {
+        return undouble(base);
+    }
+    strip("sses", 5).map(|base| base + "ss")
+        .or_else(|| if word.ends_with("ss") || word.ends_with("us") { None } else { strip("s", 4) })
+        .unwrap_or_else(|| word.to_string())
+}
+
+/// `runn` → `run`, as left behind by `running`; `ll`, `ss` and `zz` stay.
+fn undouble(mut base: String) -> String {
+    let mut tail = base.chars().rev();
+    if let (Some(last), Some(before)) = (tail.next(), tail.next()) {
+        if last == before && last.is_ascii_alphabetic() && !"aeiouylsz".contains(last) {
+            base.pop();
+        }
+    }
+    base
+}
+
+/// Lowercased, stemmed words of `text`, without stopwords.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .filter(|w| !STOPWORDS.contains(&w.as_str()))
+        .map(|w| stem(&w))
+        .collect()
+}
+
+/// BM25 index over a fixed list of tokenized passages.
+pub struct Bm25 {
+    passages: Vec<HashMap<String, usize>>,
+    lengths: Vec<usize>,
+    average_length: f64,
+    /// Number of passages containing each term.
+    document_frequency: HashMap<String, usize>,
+}
+
+impl Bm25 {
+    pub fn new(passages: impl IntoIterator<Item = Vec<String>>) -> Self {
+        let mut index = Bm25 {
+            passages: Vec::new(),
+            lengths: Vec::new(),
+            average_length: 0.0,
+            document_frequency: HashMap::new(),
+        };
+        for tokens in passages {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for token in &tokens {
+                *counts.entry(token.clone()).or_default() += 1;
+            }
+            for term in counts.keys() {
+                *index.document_frequency.entry(term.clone()).or_default() += 1;
+            }
+            index.lengths.push(tokens.len());
+            index.passages.push(counts);
+        }
+        let total: usize = index.lengths.iter().sum();
+        index.average_length = total as f64 / index.lengths.len().max(1) as f64;
+        index
+    }
+
+    fn idf(&self, term: &str) -> f64 {
+        let n = self.passages.len() as f64;
+        let df = self.document_frequency.get(term).copied().unwrap_or(0) as f64;
+        (1.0 + (n - df + 0.5) / (df + 0.5)).ln()
+    }
+
+    /// Score of passage `index` for the (tokenized) query. Repeated query
+    /// terms count once.
+    pub fn score(&self, query: &[String], index: usize) -> f64 {
+        let counts = &self.passages[index];
+        let length_ratio = self.lengths[index] as f64 / self.average_length.max(1.0);
+        let unique: HashSet<&String> = query.iter().collect();
+
+        unique.into_iter()
+            .filter_map(|term| {
+                let tf = *counts.get(term)? as f64;
+                Some(self.idf(term) * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * length_ratio)))
+            })
+            .sum()
+    }
+
+    /// Query terms that occur in passage `index`, in query order.
+    pub fn matched_terms<'q>(&self, query: &'q [String], index: usize) -> Vec<&'q str> {
+        let mut seen = HashSet::new();
+        query.iter()
+            .filter(|term| self.passages[index].contains_key(*term) && seen.insert(term.as_str()))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Indices of passages scoring above zero, best first.
+    pub fn rank(&self, query: &[String]) -> Vec<(usize, f64)> {
+        let mut scored: Vec<(usize, f64)> = (0..self.passages.len())
+            .map(|i| (i, self.score(query, i)))
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        scored
+    }
+}
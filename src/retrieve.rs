@@ -0,0 +1,220 @@
+//! Passage retrieval over a rendered page. The page is split into
+//! overlapping chunks along headings and paragraphs, and the chunks are
+//! ranked against a query with BM25. Everything runs in-process.
+
+use crate::rank::{self, Bm25};
+
+/// Rough chunk size in words; a chunk closes at the first paragraph break
+/// past it.
+const CHUNK_WORDS: usize = 120;
+/// Lines per chunk when a single paragraph is too long to keep whole.
+const LONG_PARAGRAPH_LINES: usize = 8;
+/// A chunk whose lines are mostly covered by better-ranked chunks adds
+/// little; it is dropped when this share or more is already shown.
+const MAX_COVERED_SHARE: f64 = 0.5;
+
+/// A run of page lines under one heading.
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    /// First and last line, inclusive.
+    pub start: usize,
+    pub end: usize,
+    /// Nearest heading above the chunk, without its `#` markers.
+    pub heading: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Passage {
+    pub chunk: Chunk,
+    pub score: f64,
+    /// Query terms (stemmed) found in the chunk or its heading.
+    pub terms: Vec<String>,
+}
+
+/// Heading text of `line`: Markdown-style `#` headings, as both output
+/// formats render them, and the page markers of PDFs.
+fn heading(line: &str) -> Option<String> {
+    let trimmed = line.trim();
+    let text = if trimmed.starts_with('#') {
+        trimmed.trim_start_matches('#').trim()
+    } else if trimmed.starts_with("--- page ") && trimmed.ends_with(" ---") {
+        trimmed.trim_matches('-').trim()
+    } else {
+        return None;
+    };
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// First and last line of a paragraph, inclusive.
+type LineRange = (usize, usize);
+
+/// Paragraphs of each section, under its heading. Blank lines separate
+/// paragraphs; fenced code stays whole.
+fn sections(lines: &[&str]) -> Vec<(Option<String>, Vec<LineRange>)> {
+    let mut sections = vec![(None, Vec::new())];
+    let mut paragraph: Option<LineRange> = None;
+    let mut in_fence = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        let section_heading = if in_fence { None } else { heading(line) };
+        if section_heading.is_some() || (!in_fence && line.trim().is_empty()) {
+            if let Some(p) = paragraph.take() {
+                sections.last_mut().expect("at least one section").1.push(p);
+            }
+            if let Some(h) = section_heading {
+                sections.push((Some(h), Vec::new()));
+            }
+            continue;
+        }
+        paragraph = Some(paragraph.map_or((i, i), |(start, _)| (start, i)));
+    }
+    if let Some(p) = paragraph {
+        sections.last_mut().expect("at least one section").1.push(p);
+    }
+
+    sections.retain(|(_, paragraphs)| !paragraphs.is_empty());
+    sections
+}
+
+/// Split `content` into chunks of about `CHUNK_WORDS` words. Chunks never
+/// cross a heading; consecutive chunks of a section share one paragraph
+/// (or a few lines of an over-long one) so text at a boundary is whole in
+/// at least one chunk.
+pub fn chunk(content: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    let words = |start: usize, end: usize| -> usize {
+        lines[start..=end].iter().map(|l| l.split_whitespace().count()).sum()
+    };
+
+    let mut chunks = Vec::new();
+    for (heading, paragraphs) in sections(&lines) {
+        // Over-long paragraphs become overlapping line windows first
+        let mut pieces = Vec::new();
+        for (start, end) in paragraphs {
+            if words(start, end) <= CHUNK_WORDS {
+                pieces.push((start, end));
+                continue;
+            }
+            let step = LONG_PARAGRAPH_LINES - LONG_PARAGRAPH_LINES / 4;
+            let mut window_start = start;
+            loop {
+                let window_end = (window_start + LONG_PARAGRAPH_LINES - 1).min(end);
+                pieces.push((window_start, window_end));
+                if window_end == end {
+                    break;
+                }
+                window_start += step;
+            }
+        }
+
+        let mut first = 0;
+        while first < pieces.len() {
+            let mut last = first;
+            let mut count = words(pieces[first].0, pieces[first].1);
+            while last + 1 < pieces.len() && count < CHUNK_WORDS {
+                last += 1;
+                count += words(pieces[last].0, pieces[last].1);
+            }
+            chunks.push(Chunk { start: pieces[first].0, end: pieces[last].1, heading: heading.clone() });
+            if last + 1 >= pieces.len() {
+                break;
+            }
+            // Overlap by one piece, unless the chunk was a single piece
+            first = if last > first { last } else { last + 1 };
+        }
+    }
+    chunks
+}
+
+/// The `top_k` chunks of `content` most relevant to the (tokenized)
+/// query, best first.
+pub fn retrieve(content: &str, query: &[String], top_k: usize) -> Vec<Passage> {
+    let lines: Vec<&str> = content.lines().collect();
+    let chunks = chunk(content);
+    let index = Bm25::new(chunks.iter().map(|c| {
+        let mut tokens = c.heading.as_deref().map(rank::tokenize).unwrap_or_default();
+        for line in &lines[c.start..=c.end] {
+            tokens.extend(rank::tokenize(line));
+        }
+        tokens
+    }));
+
+    let mut covered = vec![false; lines.len()];
+    let mut passages = Vec::new();
+    for (i, score) in index.rank(query) {
+        if passages.len() >= top_k {
+            break;
+        }
+        let chunk = &chunks[i];
+        let span = &mut covered[chunk.start..=chunk.end];
+        let already = span.iter().filter(|&&c| c).count();
+        if already as f64 >= span.len() as f64 * MAX_COVERED_SHARE {
+            continue;
+        }
+        span.iter_mut().for_each(|c| *c = true);
+        passages.push(Passage {
+            chunk: chunk.clone(),
+            score,
+            terms: index.matched_terms(query, i).into_iter().map(str::to_string).collect(),
+        });
+    }
+    passages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(chunks: &[Chunk]) -> Vec<(usize, usize, Option<&str>)> {
+        chunks.iter().map(|c| (c.start, c.end, c.heading.as_deref())).collect()
+    }
+
+    fn paragraph(words: usize) -> String {
+        vec!["word"; words].join(" ")
+    }
+
+    #[test]
+    fn chunks_never_cross_headings() {
+        let content = "# Intro\npara one\n\n## Setup\npara two\n\n--- page 2 ---\nthird";
+        assert_eq!(
+            bounds(&chunk(content)),
+            vec![(1, 1, Some("Intro")), (4, 4, Some("Setup")), (7, 7, Some("page 2"))]
+        );
+    }
+
+    #[test]
+    fn fenced_code_stays_whole() {
+        let content = "```\nlet a = 1;\n\n# not a heading\n```";
+        assert_eq!(bounds(&chunk(content)), vec![(0, 4, None)]);
+    }
+
+    #[test]
+    fn consecutive_chunks_share_a_paragraph() {
+        let content = vec![paragraph(50); 4].join("\n\n");
+        assert_eq!(bounds(&chunk(&content)), vec![(0, 4, None), (4, 6, None)]);
+    }
+
+    #[test]
+    fn long_paragraphs_become_overlapping_windows() {
+        let content = vec![paragraph(10); 20].join("\n");
+        assert_eq!(bounds(&chunk(&content)), vec![(0, 13, None), (6, 19, None)]);
+    }
+
+    #[test]
+    fn retrieval_ranks_by_heading_and_body() {
+        let content = "# Installing\nRun cargo with the binary name.\n\n# Usage\nCall the tool with a query.\n\n# Notes\nInstall it again to update.";
+        let query = rank::tokenize("install");
+
+        let passages = retrieve(content, &query, 5);
+        let headings: Vec<_> = passages.iter().map(|p| p.chunk.heading.as_deref()).collect();
+        assert_eq!(headings.len(), 2);
+        assert!(headings.contains(&Some("Installing")) && headings.contains(&Some("Notes")));
+        assert!(passages.iter().all(|p| !p.terms.is_empty()));
+
+        assert_eq!(retrieve(content, &query, 1).len(), 1);
+        assert!(retrieve(content, &rank::tokenize("zebra"), 5).is_empty());
+    }
+}
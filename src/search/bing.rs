@@ -0,0 +1,292 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{check_status, env_or, required_env, SafeSearch, SearchBackend, SearchError, SearchPage, SearchQuery, SearchResult, TimeRange, Vertical};
+
+const DEFAULT_ENDPOINT: &str = "https://api.bing.microsoft.com/v7.0";
+
+/// Bing Search API v7 (web, news, images and videos). Requires `BING_API_KEY`.
+pub struct BingBackend {
+    endpoint: String,
+    api_key: String,
+}
+
+/// Web search nests results under `webPages`; the news, images and videos
+/// endpoints return them at the top level.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BingResponse {
+    web_pages: Option<BingWebPages>,
+    #[serde(default)]
+    value: Vec<BingResult>,
+    total_estimated_matches: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BingWebPages {
+    #[serde(default)]
+    value: Vec<BingResult>,
+    #[serde(default)]
+    total_estimated_matches: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BingResult {
+    #[serde(default)]
+    name: String,
+    url: Option<String>,
+    #[serde(default)]
+    snippet: String,
+    description: Option<String>,
+    date_published: Option<String>,
+    /// News: the outlet(s) that published the article.
+    #[serde(default)]
+    provider: Vec<BingOrganization>,
+    /// Videos: the hosting site(s).
+    #[serde(default)]
+    publisher: Vec<BingOrganization>,
+    image: Option<BingNewsImage>,
+    /// Images: the full-size image. Videos: the video itself.
+    content_url: Option<String>,
+    host_page_url: Option<String>,
+    thumbnail_url: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    /// ISO 8601 duration, e.g. `PT4M12S`.
+    duration: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BingOrganization {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BingNewsImage {
+    thumbnail: Option<BingThumbnail>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BingThumbnail {
+    content_url: Option<String>,
+}
+
+impl BingResult {
+    fn into_search_result(self, vertical: Vertical) -> Option<SearchResult> {
+        let url = self.url.or_else(|| self.host_page_url.clone())?;
+        let thumbnail = self.thumbnail_url
+            .or_else(|| self.image.and_then(|i| i.thumbnail).and_then(|t| t.content_url));
+        let publisher = self.provider
+            .into_iter()
+            .chain(self.publisher)
+            .next()
+            .map(|org| org.name);
+        let image_url = if vertical == Vertical::Images { self.content_url } else { None };
+
+        Some(SearchResult {
+            title: self.name,
+            url,
+            snippet: self.description.unwrap_or(self.snippet),
+            publisher,
+            published: self.date_published,
+            thumbnail,
+            width: self.width.filter(|_| image_url.is_some()),
+            height: self.height.filter(|_| image_url.is_some()),
+            image_url,
+            duration: self.duration,
+            ..Default::default()
+        })
+    }
+}
+
+impl BingBackend {
+    pub fn new(endpoint: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into(), api_key: api_key.into() }
+    }
+
+    pub fn from_env() -> Result<Self, SearchError> {
+        Ok(Self::new(env_or("BING_ENDPOINT", DEFAULT_ENDPOINT), required_env("BING_API_KEY")?))
+    }
+}
+
+#[async_trait]
+impl SearchBackend for BingBackend {
+    fn name(&self) -> &'static str {
+        "bing"
+    }
+
+    async fn fetch_page(&self, client: &reqwest::Client, query: &SearchQuery, offset: usize, want: usize) -> Result<SearchPage, SearchError> {
+        let filters = &query.filters;
+
+        let mut params = vec![
+            ("q", query.query_with_site_operators()),
+            ("count", want.clamp(1, 50).to_string()),
+            ("offset", offset.to_string()),
+        ];
+        match filters.time_range {
+            Some(TimeRange::Day) => params.push(("freshness", "Day".to_string())),
+            Some(TimeRange::Week) => params.push(("freshness", "Week".to_string())),
+            Some(TimeRange::Month) => params.push(("freshness", "Month".to_string())),
+            // Bing has no "Year" value; web search accepts an explicit date
+            // range instead, the other verticals have no equivalent
+            Some(TimeRange::Year) if query.vertical == Vertical::Web => {
+                let today = chrono::Utc::now().date_naive();
+                let since = today - chrono::Duration::days(TimeRange::Year.days());
+                params.push(("freshness", format!("{}..{}", since, today)));
+            }
+            Some(TimeRange::Year) | None => {}
+        }
+        match (&filters.region, &filters.language) {
+            (Some(region), Some(language)) => params.push(("mkt", format!("{}-{}", language, region.to_uppercase()))),
+            (Some(region), None) => params.push(("cc", region.to_uppercase())),
+            (None, Some(language)) => params.push(("setLang", language.clone())),
+            (None, None) => {}
+        }
+        if let Some(safe) = filters.safe_search {
+            let level = match safe {
+                SafeSearch::Off => "Off",
+                SafeSearch::Moderate => "Moderate",
+                SafeSearch::Strict => "Strict",
+            };
+            params.push(("safeSearch", level.to_string()));
+        }
+
+        let path = match query.vertical {
+            Vertical::Web => "search",
+            Vertical::News => "news/search",
+            Vertical::Images => "images/search",
+            Vertical::Videos => "videos/search",
+        };
+
+        let response = client.get(format!("{}/{}", self.endpoint.trim_end_matches('/'), path))
+            .query(&params)
+            .header("Ocp-Apim-Subscription-Key", &self.api_key)
+            .send()
+            .await?;
+
+        let body: BingResponse = check_status(self.name(), response)?.json().await?;
+
+        let (results, total) = match body.web_pages {
+            Some(pages) => (pages.value, pages.total_estimated_matches),
+            None => (body.value, body.total_estimated_matches.unwrap_or(0)),
+        };
+        // Advance by what Bing returned, including items without a link
+        let next = offset + results.len();
+        let next_offset = (!results.is_empty() && (next as u64) < total).then_some(next);
+        let results: Vec<SearchResult> = results
+            .into_iter()
+            .filter_map(|r| r.into_search_result(query.vertical))
+            .collect();
+
+        Ok(SearchPage { results, next_offset, ..Default::default() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::mock;
+    use axum::extract::Query;
+    use axum::http::HeaderMap;
+    use axum::routing::get;
+    use std::collections::HashMap;
+
+    /// Five matches in total, served `count` at a time from `offset`.
+    async fn web_search(headers: HeaderMap, Query(params): Query<HashMap<String, String>>) -> axum::Json<serde_json::Value> {
+        assert_eq!(headers["Ocp-Apim-Subscription-Key"], "key");
+        let offset: usize = params["offset"].parse().unwrap();
+        let count: usize = params["count"].parse().unwrap();
+        let results: Vec<_> = (offset..(offset + count).min(5))
+            .map(|n| serde_json::json!({
+                "name": format!("r{}", n),
+                "url": format!("https://example.com/{}", n),
+                "snippet": "snippet",
+                "dateLastCrawled": "2024-01-02",
+            }))
+            .collect();
+        axum::Json(serde_json::json!({
+            "webPages": {"value": results, "totalEstimatedMatches": 5},
+        }))
+    }
+
+    async fn backend() -> BingBackend {
+        let base = mock::serve(axum::Router::new().route("/search", get(web_search))).await;
+        BingBackend::new(base, "key")
+    }
+
+    #[tokio::test]
+    async fn parses_results() {
+        let page = backend().await.search(&reqwest::Client::new(), &mock::query(2)).await.unwrap();
+
+        let titles: Vec<&str> = page.results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["r0", "r1"]);
+        assert_eq!(page.results[0].url, "https://example.com/0");
+        assert_eq!(page.next_offset, Some(2));
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_estimated_total() {
+        let query = SearchQuery { offset: 3, ..mock::query(10) };
+
+        let page = backend().await.search(&reqwest::Client::new(), &query).await.unwrap();
+
+        let titles: Vec<&str> = page.results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["r3", "r4"]);
+        assert_eq!(page.next_offset, None);
+    }
+
+    #[tokio::test]
+    async fn too_many_requests_is_blocked() {
+        let base = mock::serve(axum::Router::new().route("/search", get(|| async { mock::too_many_requests() }))).await;
+
+        let error = BingBackend::new(base, "key").search(&reqwest::Client::new(), &mock::query(5)).await.err();
+        assert!(matches!(error, Some(SearchError::Blocked { backend: "bing", retry_after_secs: 30, .. })));
+    }
+
+    #[tokio::test]
+    async fn news_is_mapped() {
+        let base = mock::serve(axum::Router::new().route("/news/search", get(|| async {
+            axum::Json(serde_json::json!({
+                "value": [
+                    {
+                        "name": "Headline", "url": "https://news.example/a", "description": "Summary",
+                        "datePublished": "2024-03-04T05:06:07Z", "provider": [{"name": "Example News"}],
+                        "image": {"thumbnail": {"contentUrl": "https://img.example/a.jpg"}},
+                    },
+                    {"name": "No link"},
+                ],
+                "totalEstimatedMatches": 10,
+            }))
+        }))).await;
+
+        let query = SearchQuery { vertical: Vertical::News, ..mock::query(1) };
+        let page = BingBackend::new(base, "key").search(&reqwest::Client::new(), &query).await.unwrap();
+
+        let result = &page.results[0];
+        assert_eq!((result.title.as_str(), result.url.as_str(), result.snippet.as_str()), ("Headline", "https://news.example/a", "Summary"));
+        assert_eq!(result.publisher.as_deref(), Some("Example News"));
+        assert_eq!(result.published.as_deref(), Some("2024-03-04T05:06:07Z"));
+        assert_eq!(result.thumbnail.as_deref(), Some("https://img.example/a.jpg"));
+        assert_eq!(result.image_url, None);
+    }
+
+    #[tokio::test]
+    async fn dropped_items_still_advance_the_offset() {
+        let base = mock::serve(axum::Router::new().route("/news/search", get(|| async {
+            axum::Json(serde_json::json!({
+                "value": [{"name": "One", "url": "https://news.example/1"}, {"name": "No link"}],
+                "totalEstimatedMatches": 10,
+            }))
+        }))).await;
+
+        let query = SearchQuery { vertical: Vertical::News, ..mock::query(1) };
+        let page = BingBackend::new(base, "key").search(&reqwest::Client::new(), &query).await.unwrap();
+
+        assert_eq!(page.results.len(), 1);
+        assert_eq!(page.next_offset, Some(2));
+    }
+}
+
@@ -0,0 +1,298 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{check_status, env_or, required_env, SafeSearch, SearchBackend, SearchError, SearchPage, SearchQuery, SearchResult, Vertical};
+
+const DEFAULT_ENDPOINT: &str = "https://api.search.brave.com/res/v1";
+/// Maximum `count` Brave accepts per request.
+const PAGE_SIZE: usize = 20;
+const MAX_PAGE: usize = 9;
+
+/// Brave Search API. Requires `BRAVE_API_KEY`.
+pub struct BraveBackend {
+    endpoint: String,
+    api_key: String,
+}
+
+/// Web search nests results under `web`; the news, images and videos
+/// endpoints return them at the top level.
+#[derive(Debug, Deserialize)]
+struct BraveResponse {
+    query: Option<BraveQuery>,
+    web: Option<BraveWeb>,
+    #[serde(default)]
+    results: Vec<BraveResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveQuery {
+    #[serde(default)]
+    more_results_available: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveWeb {
+    #[serde(default)]
+    results: Vec<BraveResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveResult {
+    #[serde(default)]
+    title: String,
+    url: String,
+    #[serde(default)]
+    description: String,
+    page_age: Option<String>,
+    age: Option<String>,
+    /// Images: domain the image was found on.
+    source: Option<String>,
+    meta_url: Option<BraveMetaUrl>,
+    thumbnail: Option<BraveThumbnail>,
+    properties: Option<BraveImageProperties>,
+    video: Option<BraveVideo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveMetaUrl {
+    hostname: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveThumbnail {
+    src: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveImageProperties {
+    url: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveVideo {
+    duration: Option<String>,
+    publisher: Option<String>,
+    creator: Option<String>,
+}
+
+impl From<BraveResult> for SearchResult {
+    fn from(r: BraveResult) -> Self {
+        let (image_url, width, height) = match r.properties {
+            Some(p) => (p.url, p.width, p.height),
+            None => (None, None, None),
+        };
+        let (thumbnail, thumb_width, thumb_height) = match r.thumbnail {
+            Some(t) => (t.src, t.width, t.height),
+            None => (None, None, None),
+        };
+        let (duration, video_publisher) = match r.video {
+            Some(v) => (v.duration, v.publisher.or(v.creator)),
+            None => (None, None),
+        };
+
+        SearchResult {
+            title: r.title,
+            url: r.url,
+            snippet: r.description,
+            publisher: video_publisher
+                .or(r.source)
+                .or_else(|| r.meta_url.and_then(|m| m.hostname)),
+            published: r.page_age.or(r.age),
+            thumbnail,
+            width: width.or(thumb_width).filter(|_| image_url.is_some()),
+            height: height.or(thumb_height).filter(|_| image_url.is_some()),
+            image_url,
+            duration,
+            ..Default::default()
+        }
+    }
+}
+
+impl BraveBackend {
+    pub fn new(endpoint: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into(), api_key: api_key.into() }
+    }
+
+    pub fn from_env() -> Result<Self, SearchError> {
+        Ok(Self::new(env_or("BRAVE_ENDPOINT", DEFAULT_ENDPOINT), required_env("BRAVE_API_KEY")?))
+    }
+}
+
+#[async_trait]
+impl SearchBackend for BraveBackend {
+    fn name(&self) -> &'static str {
+        "brave"
+    }
+
+    async fn fetch_page(&self, client: &reqwest::Client, query: &SearchQuery, offset: usize, _want: usize) -> Result<SearchPage, SearchError> {
+        // Brave's `offset` counts pages of `count` results, up to page 9.
+        // The images endpoint has no offset at all and returns one batch.
+        let page = offset / PAGE_SIZE;
+        if page > MAX_PAGE || (query.vertical == Vertical::Images && offset > 0) {
+            return Ok(SearchPage::default());
+        }
+        let filters = &query.filters;
+
+        let mut params = vec![
+            ("q", query.query_with_site_operators()),
+            ("count", PAGE_SIZE.to_string()),
+        ];
+        if query.vertical != Vertical::Images {
+            params.push(("offset", page.to_string()));
+            if let Some(range) = filters.time_range {
+                params.push(("freshness", format!("p{}", range.letter())));
+            }
+        }
+        if let Some(region) = &filters.region {
+            params.push(("country", region.clone()));
+        }
+        if let Some(language) = &filters.language {
+            params.push(("search_lang", language.clone()));
+        }
+        if let Some(safe) = filters.safe_search {
+            let level = match (safe, query.vertical) {
+                (SafeSearch::Off, _) => "off",
+                // Image search only knows "off" and "strict"
+                (SafeSearch::Moderate, Vertical::Images) => "strict",
+                (SafeSearch::Moderate, _) => "moderate",
+                (SafeSearch::Strict, _) => "strict",
+            };
+            params.push(("safesearch", level.to_string()));
+        }
+
+        let path = match query.vertical {
+            Vertical::Web => "web",
+            Vertical::News => "news",
+            Vertical::Images => "images",
+            Vertical::Videos => "videos",
+        };
+
+        let response = client.get(format!("{}/{}/search", self.endpoint.trim_end_matches('/'), path))
+            .query(&params)
+            .header("Accept", "application/json")
+            .header("X-Subscription-Token", &self.api_key)
+            .send()
+            .await?;
+
+        let body: BraveResponse = check_status(self.name(), response)?.json().await?;
+
+        let more = query.vertical != Vertical::Images
+            && body.query.is_some_and(|q| q.more_results_available)
+            && page < MAX_PAGE;
+        let results: Vec<SearchResult> = body.web
+            .map(|web| web.results)
+            .unwrap_or(body.results)
+            .into_iter()
+            .skip(offset % PAGE_SIZE)
+            .map(SearchResult::from)
+            .collect();
+        let next_offset = more.then_some((page + 1) * PAGE_SIZE);
+
+        Ok(SearchPage { results, next_offset, ..Default::default() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::mock;
+    use axum::extract::Query;
+    use axum::http::HeaderMap;
+    use axum::routing::get;
+    use std::collections::HashMap;
+
+    /// Full pages of `PAGE_SIZE` results titled `o<offset>-<n>`; more
+    /// results are available until page 2.
+    async fn web_search(headers: HeaderMap, Query(params): Query<HashMap<String, String>>) -> axum::Json<serde_json::Value> {
+        assert_eq!(headers["X-Subscription-Token"], "key");
+        let page: usize = params["offset"].parse().unwrap();
+        let results: Vec<_> = (0..PAGE_SIZE)
+            .map(|n| serde_json::json!({
+                "title": format!("o{}-{}", page, n),
+                "url": format!("https://example.com/{}/{}", page, n),
+                "description": "snippet",
+                "page_age": "2024-01-02T00:00:00",
+                "meta_url": {"hostname": "example.com"},
+            }))
+            .collect();
+        axum::Json(serde_json::json!({
+            "query": {"more_results_available": page < 2},
+            "web": {"results": results},
+        }))
+    }
+
+    async fn backend() -> BraveBackend {
+        let base = mock::serve(axum::Router::new().route("/web/search", get(web_search))).await;
+        BraveBackend::new(base, "key")
+    }
+
+    #[tokio::test]
+    async fn parses_results() {
+        let page = backend().await.search(&reqwest::Client::new(), &mock::query(3)).await.unwrap();
+
+        assert_eq!(page.results.len(), 3);
+        let first = &page.results[0];
+        assert_eq!((first.title.as_str(), first.url.as_str()), ("o0-0", "https://example.com/0/0"));
+        assert_eq!(first.publisher.as_deref(), Some("example.com"));
+        assert_eq!(first.published.as_deref(), Some("2024-01-02T00:00:00"));
+        assert_eq!(page.next_offset, Some(3));
+    }
+
+    #[tokio::test]
+    async fn offsets_map_onto_page_numbers() {
+        let query = SearchQuery { offset: PAGE_SIZE - 1, ..mock::query(2) };
+
+        let page = backend().await.search(&reqwest::Client::new(), &query).await.unwrap();
+
+        let titles: Vec<&str> = page.results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["o0-19", "o1-0"]);
+        assert_eq!(page.next_offset, Some(PAGE_SIZE + 1));
+    }
+
+    #[tokio::test]
+    async fn stops_when_no_more_results_are_available() {
+        let query = SearchQuery { offset: 2 * PAGE_SIZE, ..mock::query(50) };
+
+        let page = backend().await.search(&reqwest::Client::new(), &query).await.unwrap();
+
+        assert_eq!(page.results.len(), PAGE_SIZE);
+        assert_eq!(page.next_offset, None);
+    }
+
+    #[tokio::test]
+    async fn too_many_requests_is_blocked() {
+        let base = mock::serve(axum::Router::new().route("/web/search", get(|| async { mock::too_many_requests() }))).await;
+
+        let error = BraveBackend::new(base, "key").search(&reqwest::Client::new(), &mock::query(5)).await.err();
+        assert!(matches!(error, Some(SearchError::Blocked { backend: "brave", retry_after_secs: 30, .. })));
+    }
+
+    #[tokio::test]
+    async fn news_is_mapped() {
+        let base = mock::serve(axum::Router::new().route("/news/search", get(|| async {
+            axum::Json(serde_json::json!({
+                "query": {"more_results_available": false},
+                "results": [{
+                    "title": "Headline", "url": "https://news.example/a", "description": "Summary", "age": "2 hours ago",
+                    "meta_url": {"hostname": "news.example"}, "thumbnail": {"src": "https://img.example/a.jpg"},
+                }],
+            }))
+        }))).await;
+
+        let query = SearchQuery { vertical: Vertical::News, ..mock::query(5) };
+        let page = BraveBackend::new(base, "key").search(&reqwest::Client::new(), &query).await.unwrap();
+
+        let result = &page.results[0];
+        assert_eq!((result.title.as_str(), result.url.as_str(), result.snippet.as_str()), ("Headline", "https://news.example/a", "Summary"));
+        assert_eq!(result.publisher.as_deref(), Some("news.example"));
+        assert_eq!(result.published.as_deref(), Some("2 hours ago"));
+        assert_eq!(result.thumbnail.as_deref(), Some("https://img.example/a.jpg"));
+        assert_eq!((result.image_url.as_deref(), result.width), (None, None));
+        assert_eq!(page.next_offset, None);
+    }
+}
+
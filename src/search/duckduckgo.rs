@@ -0,0 +1,572 @@
+use async_trait::async_trait;
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde::Deserialize;
+use url::Url;
+
+use super::urls::normalize_url;
+use super::{blocked, check_status, env_or, SafeSearch, SearchBackend, SearchError, SearchFilters, SearchPage, SearchQuery, SearchResult, TimeRange, Vertical};
+
+const DEFAULT_ENDPOINT: &str = "https://html.duckduckgo.com/html/";
+const DEFAULT_API_ENDPOINT: &str = "https://duckduckgo.com";
+
+/// Fragments of DDG's bot-challenge ("anomaly") page and CAPTCHA widgets.
+const BLOCK_MARKERS: &[&str] = &[
+    "anomaly-modal",
+    "anomaly.js",
+    "challenge-form",
+    "bots use DuckDuckGo too",
+    "g-recaptcha",
+    "h-captcha",
+];
+
+/// Fragments that only appear on a genuine "nothing found" result page.
+const NO_RESULTS_MARKERS: &[&str] = &["no-results", "No results."];
+
+/// DDG's `kl` region codes by country. A country with several codes lists
+/// its default first; any other code `kl` is silently ignored by DDG.
+const REGION_CODES: &[(&str, &[&str])] = &[
+    ("ar", &["ar-es"]), ("at", &["at-de"]), ("au", &["au-en"]), ("be", &["be-nl", "be-fr"]),
+    ("bg", &["bg-bg"]), ("br", &["br-pt"]), ("ca", &["ca-en", "ca-fr"]), ("ch", &["ch-de", "ch-fr", "ch-it"]),
+    ("cl", &["cl-es"]), ("cn", &["cn-zh"]), ("co", &["co-es"]), ("cz", &["cz-cs"]), ("de", &["de-de"]),
+    ("dk", &["dk-da"]), ("ee", &["ee-et"]), ("es", &["es-es", "ct-ca"]), ("fi", &["fi-fi"]), ("fr", &["fr-fr"]),
+    ("gb", &["uk-en"]), ("gr", &["gr-el"]), ("hk", &["hk-tzh"]), ("hr", &["hr-hr"]), ("hu", &["hu-hu"]),
+    ("id", &["id-id", "id-en"]), ("ie", &["ie-en"]), ("il", &["il-he"]), ("in", &["in-en"]), ("it", &["it-it"]),
+    ("jp", &["jp-jp"]), ("kr", &["kr-kr"]), ("lt", &["lt-lt"]), ("lv", &["lv-lv"]), ("mx", &["mx-es"]),
+    ("my", &["my-ms", "my-en"]), ("nl", &["nl-nl"]), ("no", &["no-no"]), ("nz", &["nz-en"]), ("pe", &["pe-es"]),
+    ("ph", &["ph-en", "ph-tl"]), ("pl", &["pl-pl"]), ("pt", &["pt-pt"]), ("ro", &["ro-ro"]), ("ru", &["ru-ru"]),
+    ("se", &["se-sv"]), ("sg", &["sg-en"]), ("si", &["sl-sl"]), ("sk", &["sk-sk"]), ("th", &["th-th"]),
+    ("tr", &["tr-tr"]), ("tw", &["tw-tzh"]), ("ua", &["ua-uk"]), ("uk", &["uk-en"]), ("us", &["us-en", "us-es"]),
+    ("vn", &["vn-vi"]), ("za", &["za-en"]),
+];
+
+lazy_static::lazy_static! {
+    static ref VQD_TOKEN: Regex = Regex::new(r#"vqd=["']?([\d-]+)"#).unwrap();
+}
+
+/// Scrapes the DuckDuckGo HTML endpoint for web results, and uses the JSON
+/// endpoints behind duckduckgo.com (`news.js`, `i.js`, `v.js`) for the
+/// other verticals. Needs no API key.
+pub struct DuckDuckGoBackend {
+    endpoint: String,
+    api_endpoint: String,
+}
+
+/// Shared shape of the `news.js`, `i.js` and `v.js` responses.
+#[derive(Debug, Deserialize)]
+struct VerticalResponse {
+    #[serde(default)]
+    results: Vec<VerticalResult>,
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerticalResult {
+    #[serde(default)]
+    title: String,
+    url: Option<String>,
+    excerpt: Option<String>,
+    description: Option<String>,
+    /// News: outlet name. Images: the engine the image came from.
+    source: Option<String>,
+    publisher: Option<String>,
+    /// News: publication time as a Unix timestamp.
+    date: Option<i64>,
+    /// Videos: publication time as an ISO 8601 string.
+    published: Option<String>,
+    /// News: thumbnail. Images: the full-size image.
+    image: Option<String>,
+    thumbnail: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    duration: Option<String>,
+    /// Videos: link to the video page.
+    content: Option<String>,
+    images: Option<VideoImages>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VideoImages {
+    medium: Option<String>,
+    small: Option<String>,
+}
+
+impl VerticalResult {
+    fn into_search_result(self, vertical: Vertical) -> Option<SearchResult> {
+        match vertical {
+            Vertical::Images => Some(SearchResult {
+                title: self.title,
+                url: self.url?,
+                thumbnail: self.thumbnail,
+                image_url: self.image,
+                width: self.width,
+                height: self.height,
+                publisher: self.source,
+                ..Default::default()
+            }),
+            Vertical::Videos => Some(SearchResult {
+                title: self.title,
+                url: self.content.or(self.url)?,
+                snippet: self.description.unwrap_or_default(),
+                publisher: self.publisher,
+                published: self.published,
+                thumbnail: self.images.and_then(|i| i.medium.or(i.small)),
+                duration: self.duration,
+                ..Default::default()
+            }),
+            Vertical::News | Vertical::Web => Some(SearchResult {
+                title: self.title,
+                url: self.url?,
+                snippet: self.excerpt.unwrap_or_default(),
+                publisher: self.source,
+                published: self.date
+                    .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+                    .map(|dt| dt.to_rfc3339()),
+                thumbnail: self.image,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+impl DuckDuckGoBackend {
+    pub fn new(endpoint: impl Into<String>, api_endpoint: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into(), api_endpoint: api_endpoint.into() }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            env_or("DUCKDUCKGO_ENDPOINT", DEFAULT_ENDPOINT),
+            env_or("DUCKDUCKGO_API_ENDPOINT", DEFAULT_API_ENDPOINT),
+        )
+    }
+
+    /// DDG's `kl` region code, e.g. `us-en` or `de-de`. The requested
+    /// language picks between a country's codes when DDG has one for it;
+    /// countries DDG does not know get no region.
+    fn region_code(filters: &SearchFilters) -> Option<String> {
+        let region = filters.region.as_deref()?;
+        let (_, codes) = REGION_CODES.iter().find(|(country, _)| *country == region)?;
+        let code = filters.language.as_deref()
+            .and_then(|language| codes.iter().find(|code| code.ends_with(&format!("-{}", language))))
+            .unwrap_or(&codes[0]);
+        Some(code.to_string())
+    }
+
+    fn safe_search_code(safe: SafeSearch) -> &'static str {
+        match safe {
+            SafeSearch::Off => "-2",
+            SafeSearch::Moderate => "-1",
+            SafeSearch::Strict => "1",
+        }
+    }
+
+    /// The JSON endpoints need a per-query `vqd` token that is embedded in
+    /// the regular search page.
+    async fn vqd_token(&self, client: &reqwest::Client, query: &str) -> Result<String, SearchError> {
+        let response = client.get(format!("{}/", self.api_endpoint.trim_end_matches('/')))
+            .query(&[("q", query)])
+            .send()
+            .await?;
+        let html = check_status(self.name(), response)?.text().await?;
+
+        match VQD_TOKEN.captures(&html).and_then(|caps| caps.get(1)) {
+            Some(m) => Ok(m.as_str().to_string()),
+            None if Self::has_block_marker(&html) => Err(blocked(self.name(), "served a CAPTCHA/anomaly page", None)),
+            None => Err(SearchError::Parse("DuckDuckGo did not return a vqd token".to_string())),
+        }
+    }
+
+    fn has_block_marker(html: &str) -> bool {
+        BLOCK_MARKERS.iter().any(|marker| html.contains(marker))
+    }
+
+    /// Explain why an HTML page that yielded no results looks like a block
+    /// rather than an honest empty result set. Later pages may legitimately
+    /// run dry without a notice, so the markup heuristic only applies to the
+    /// first page.
+    fn block_reason(html: &str, status: reqwest::StatusCode, first_page: bool) -> Option<&'static str> {
+        if Self::has_block_marker(html) {
+            Some("served a CAPTCHA/anomaly page")
+        } else if status == reqwest::StatusCode::ACCEPTED {
+            Some("answered with HTTP 202 instead of a result page")
+        } else if first_page && !NO_RESULTS_MARKERS.iter().any(|marker| html.contains(marker)) {
+            Some("returned a page with neither results nor a \"no results\" notice")
+        } else {
+            None
+        }
+    }
+
+    async fn fetch_vertical(&self, client: &reqwest::Client, query: &SearchQuery, offset: usize) -> Result<SearchPage, SearchError> {
+        let q = query.query_with_site_operators();
+        let vqd = self.vqd_token(client, &q).await?;
+        let filters = &query.filters;
+
+        let path = match query.vertical {
+            Vertical::Images => "i.js",
+            Vertical::Videos => "v.js",
+            Vertical::News | Vertical::Web => "news.js",
+        };
+
+        let mut params = vec![
+            ("q", q),
+            ("vqd", vqd),
+            ("o", "json".to_string()),
+            ("s", offset.to_string()),
+            ("l", Self::region_code(filters).unwrap_or_else(|| "wt-wt".to_string())),
+        ];
+        if let Some(safe) = filters.safe_search {
+            params.push(("p", Self::safe_search_code(safe).to_string()));
+        }
+        if let Some(range) = filters.time_range {
+            if query.vertical == Vertical::Images {
+                // Image search takes time filters in its `f` filter string
+                let period = match range {
+                    TimeRange::Day => "Day",
+                    TimeRange::Week => "Week",
+                    TimeRange::Month => "Month",
+                    TimeRange::Year => "Year",
+                };
+                params.push(("f", format!("time:{}", period)));
+            } else {
+                params.push(("df", range.letter().to_string()));
+            }
+        }
+
+        let response = client.get(format!("{}/{}", self.api_endpoint.trim_end_matches('/'), path))
+            .query(&params)
+            .header("Accept", "application/json")
+            .header("Referer", format!("{}/", self.api_endpoint.trim_end_matches('/')))
+            .send()
+            .await?;
+
+        let body: VerticalResponse = check_status(self.name(), response)?.json().await?;
+
+        // Count what the endpoint returned, not what survived mapping, or a
+        // dropped item would make the next page overlap this one
+        let next_offset = body.next.is_some().then_some(offset + body.results.len());
+        let results: Vec<SearchResult> = body.results
+            .into_iter()
+            .filter_map(|r| r.into_search_result(query.vertical))
+            .collect();
+
+        Ok(SearchPage { results, next_offset, ..Default::default() })
+    }
+
+    /// Parse the result page. `base` is the URL the page was served from and
+    /// is used to resolve protocol-relative and relative result links.
+    /// Also reports whether the page links to a further page of results.
+    fn parse_search_results(html: &str, base: &Url) -> Result<(Vec<SearchResult>, bool), SearchError> {
+        let document = Html::parse_document(html);
+        let result_selector = Selector::parse("div.result").map_err(|e| SearchError::Parse(format!("CSS selector error: {}", e)))?;
+        let title_selector = Selector::parse("a.result__a").map_err(|e| SearchError::Parse(format!("CSS selector error: {}", e)))?;
+        let snippet_selector = Selector::parse("a.result__snippet").map_err(|e| SearchError::Parse(format!("CSS selector error: {}", e)))?;
+        let next_selector = Selector::parse("div.nav-link form input[name=\"s\"]").map_err(|e| SearchError::Parse(format!("CSS selector error: {}", e)))?;
+
+        let mut results = Vec::new();
+
+        for result in document.select(&result_selector) {
+            let title = result
+                .select(&title_selector)
+                .next()
+                .map(|el| el.text().collect::<String>().trim().to_string())
+                .unwrap_or_else(|| "Untitled".to_string());
+
+            let url = result
+                .select(&title_selector)
+                .next()
+                .and_then(|el| el.value().attr("href"))
+                .and_then(|href| normalize_url(href, base))
+                .unwrap_or_default();
+
+            let snippet = result
+                .select(&snippet_selector)
+                .next()
+                .map(|el| el.text().collect::<String>().trim().to_string())
+                .unwrap_or_default();
+
+            if !url.is_empty() {
+                results.push(SearchResult { title, url, snippet, ..Default::default() });
+            }
+        }
+
+        let has_next = document.select(&next_selector).next().is_some();
+
+        Ok((results, has_next))
+    }
+
+    /// Map filters onto DDG's `kl` (region), `df` (date) and `kp` (safe search).
+    fn filter_params(query: &SearchQuery) -> Vec<(&'static str, String)> {
+        let filters = &query.filters;
+        let mut params = vec![("q", query.query_with_site_operators())];
+
+        if let Some(kl) = Self::region_code(filters) {
+            params.push(("kl", kl));
+        }
+        if let Some(range) = filters.time_range {
+            params.push(("df", range.letter().to_string()));
+        }
+        if let Some(safe) = filters.safe_search {
+            params.push(("kp", Self::safe_search_code(safe).to_string()));
+        }
+
+        params
+    }
+}
+
+#[async_trait]
+impl SearchBackend for DuckDuckGoBackend {
+    fn name(&self) -> &'static str {
+        "duckduckgo"
+    }
+
+    async fn fetch_page(&self, client: &reqwest::Client, query: &SearchQuery, offset: usize, _want: usize) -> Result<SearchPage, SearchError> {
+        if query.vertical != Vertical::Web {
+            return self.fetch_vertical(client, query, offset).await;
+        }
+
+        let mut params = Self::filter_params(query);
+
+        // The first page is a plain GET; later pages are the same form posts
+        // the "Next" button submits, where `s` is the result offset.
+        let request = if offset == 0 {
+            client.get(&self.endpoint).query(&params)
+        } else {
+            params.extend([
+                ("s", offset.to_string()),
+                ("dc", (offset + 1).to_string()),
+                ("v", "l".to_string()),
+                ("o", "json".to_string()),
+                ("api", "d.js".to_string()),
+            ]);
+            client.post(&self.endpoint).form(&params)
+        };
+
+        let response = request
+            .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
+            .send()
+            .await?;
+
+        let response = check_status(self.name(), response)?;
+        let status = response.status();
+        let base = response.url().clone();
+        let html = response.text().await?;
+
+        let (results, has_next) = Self::parse_search_results(&html, &base)?;
+
+        if results.is_empty() {
+            if let Some(reason) = Self::block_reason(&html, status, offset == 0) {
+                return Err(blocked(self.name(), reason, None));
+            }
+        }
+        let next_offset = has_next.then_some(offset + results.len());
+
+        Ok(SearchPage { results, next_offset, ..Default::default() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::mock;
+    use axum::extract::Form;
+    use axum::routing::get;
+    use std::collections::HashMap;
+
+    fn result_page(titles: &[&str], has_next: bool) -> String {
+        let mut html = String::from("<html><body>");
+        for title in titles {
+            html.push_str(&format!(
+                "<div class=\"result\"><a class=\"result__a\" href=\"//duckduckgo.com/l/?uddg=https%3A%2F%2Fexample.com%2F{0}%3Futm_source%3Dddg&amp;rut=x\">{0}</a>\
+                 <a class=\"result__snippet\">About {0}</a></div>",
+                title
+            ));
+        }
+        if has_next {
+            html.push_str("<div class=\"nav-link\"><form><input name=\"s\" value=\"10\"></form></div>");
+        }
+        html + "</body></html>"
+    }
+
+    async fn backend(router: axum::Router) -> DuckDuckGoBackend {
+        let base = mock::serve(router).await;
+        DuckDuckGoBackend::new(format!("{}/html/", base), base)
+    }
+
+    fn region(region: &str, language: Option<&str>) -> Option<String> {
+        let filters = SearchFilters {
+            region: Some(region.to_string()),
+            language: language.map(str::to_string),
+            ..Default::default()
+        };
+        DuckDuckGoBackend::region_code(&filters)
+    }
+
+    #[test]
+    fn region_codes_come_from_ddg_table() {
+        assert_eq!(region("de", None).as_deref(), Some("de-de"));
+        assert_eq!(region("de", Some("en")).as_deref(), Some("de-de"));
+        assert_eq!(region("jp", Some("en")).as_deref(), Some("jp-jp"));
+        assert_eq!(region("gb", None).as_deref(), Some("uk-en"));
+        assert_eq!(region("ch", Some("fr")).as_deref(), Some("ch-fr"));
+        assert_eq!(region("ca", Some("fr")).as_deref(), Some("ca-fr"));
+        assert_eq!(region("zz", Some("en")), None);
+    }
+
+    #[test]
+    fn block_reason_tells_blocks_from_empty_results() {
+        use reqwest::StatusCode;
+        let reason = DuckDuckGoBackend::block_reason;
+
+        assert_eq!(reason("<div id=\"anomaly-modal\">", StatusCode::OK, false), Some("served a CAPTCHA/anomaly page"));
+        assert_eq!(reason("<div class=\"g-recaptcha\">", StatusCode::OK, true), Some("served a CAPTCHA/anomaly page"));
+        assert_eq!(reason("", StatusCode::ACCEPTED, false), Some("answered with HTTP 202 instead of a result page"));
+        assert!(reason("<html></html>", StatusCode::OK, true).is_some());
+        assert_eq!(reason("<div class=\"no-results\">No results.</div>", StatusCode::OK, true), None);
+        // Later pages may run dry without a notice
+        assert_eq!(reason("<html></html>", StatusCode::OK, false), None);
+    }
+
+    #[tokio::test]
+    async fn parses_results_and_unwraps_redirects() {
+        let backend = backend(axum::Router::new().route("/html/", get(|| async {
+            axum::response::Html(result_page(&["one", "two"], true))
+        }))).await;
+
+        let page = backend.search(&reqwest::Client::new(), &mock::query(2)).await.unwrap();
+
+        let urls: Vec<&str> = page.results.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, ["https://example.com/one", "https://example.com/two"]);
+        assert_eq!(page.results[0].title, "one");
+        assert_eq!(page.results[0].snippet, "About one");
+        assert_eq!(page.next_offset, Some(2));
+    }
+
+    #[tokio::test]
+    async fn later_pages_are_form_posts_with_the_offset() {
+        let backend = backend(axum::Router::new().route(
+            "/html/",
+            get(|| async { axum::response::Html(result_page(&["one", "two"], true)) })
+                .post(|Form(form): Form<HashMap<String, String>>| async move {
+                    let title = format!("from{}", form["s"]);
+                    axum::response::Html(result_page(&[title.as_str(), "last"], false))
+                }),
+        )).await;
+
+        let page = backend.search(&reqwest::Client::new(), &mock::query(4)).await.unwrap();
+
+        let titles: Vec<&str> = page.results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["one", "two", "from2", "last"]);
+        assert_eq!(page.next_offset, None);
+    }
+
+    #[tokio::test]
+    async fn too_many_requests_is_blocked() {
+        let backend = backend(axum::Router::new().route("/html/", get(|| async { mock::too_many_requests() }))).await;
+
+        match backend.search(&reqwest::Client::new(), &mock::query(5)).await {
+            Err(SearchError::Blocked { backend, retry_after_secs, .. }) => {
+                assert_eq!(backend, "duckduckgo");
+                assert_eq!(retry_after_secs, 30);
+            }
+            other => panic!("expected Blocked, got {:?}", other.map(|p| p.results.len())),
+        }
+    }
+
+    #[tokio::test]
+    async fn anomaly_page_is_blocked() {
+        let backend = backend(axum::Router::new().route("/html/", get(|| async {
+            axum::response::Html("<div class=\"anomaly-modal\">bots use DuckDuckGo too</div>")
+        }))).await;
+
+        let error = backend.search(&reqwest::Client::new(), &mock::query(5)).await.err();
+        assert!(matches!(error, Some(SearchError::Blocked { .. })));
+    }
+
+    #[tokio::test]
+    async fn news_uses_the_vqd_token() {
+        let backend = backend(axum::Router::new()
+            .route("/", get(|| async { axum::response::Html("<script>vqd=\"4-1234\";</script>") }))
+            .route("/news.js", get(|params: axum::extract::Query<HashMap<String, String>>| async move {
+                assert_eq!(params["vqd"], "4-1234");
+                axum::Json(serde_json::json!({
+                    "results": [{"title": "Headline", "url": "https://news.example/a", "excerpt": "Text", "source": "Example News", "date": 0}]
+                }))
+            }))).await;
+
+        let query = SearchQuery { vertical: Vertical::News, ..mock::query(5) };
+        let page = backend.search(&reqwest::Client::new(), &query).await.unwrap();
+
+        assert_eq!(page.results.len(), 1);
+        assert_eq!(page.results[0].publisher.as_deref(), Some("Example News"));
+        assert_eq!(page.results[0].published.as_deref(), Some("1970-01-01T00:00:00+00:00"));
+        assert_eq!(page.next_offset, None);
+    }
+
+    /// A DDG backend whose `path` endpoint serves `body` for any vqd.
+    async fn vertical_backend(path: &'static str, body: serde_json::Value) -> DuckDuckGoBackend {
+        backend(axum::Router::new()
+            .route("/", get(|| async { axum::response::Html("<script>vqd=\"4-1234\";</script>") }))
+            .route(path, get(move || async move { axum::Json(body) })))
+            .await
+    }
+
+    #[tokio::test]
+    async fn images_are_mapped() {
+        let backend = vertical_backend("/i.js", serde_json::json!({
+            "results": [{
+                "title": "A crab", "url": "https://example.com/crab", "image": "https://img.example/crab.png",
+                "thumbnail": "https://img.example/crab-small.png", "width": 800, "height": 600, "source": "Bing",
+            }]
+        })).await;
+
+        let query = SearchQuery { vertical: Vertical::Images, ..mock::query(5) };
+        let result = &backend.search(&reqwest::Client::new(), &query).await.unwrap().results[0];
+
+        assert_eq!(result.url, "https://example.com/crab");
+        assert_eq!(result.image_url.as_deref(), Some("https://img.example/crab.png"));
+        assert_eq!(result.thumbnail.as_deref(), Some("https://img.example/crab-small.png"));
+        assert_eq!((result.width, result.height), (Some(800), Some(600)));
+        assert_eq!(result.publisher.as_deref(), Some("Bing"));
+    }
+
+    #[tokio::test]
+    async fn videos_are_mapped() {
+        let backend = vertical_backend("/v.js", serde_json::json!({
+            "results": [{
+                "title": "Rust in 100 seconds", "content": "https://video.example/watch?v=1", "description": "Intro",
+                "publisher": "Video Site", "published": "2024-05-01T00:00:00Z", "duration": "2:21",
+                "images": {"medium": "https://img.example/m.jpg", "small": "https://img.example/s.jpg"},
+            }]
+        })).await;
+
+        let query = SearchQuery { vertical: Vertical::Videos, ..mock::query(5) };
+        let result = &backend.search(&reqwest::Client::new(), &query).await.unwrap().results[0];
+
+        assert_eq!(result.url, "https://video.example/watch?v=1");
+        assert_eq!(result.snippet, "Intro");
+        assert_eq!(result.publisher.as_deref(), Some("Video Site"));
+        assert_eq!(result.published.as_deref(), Some("2024-05-01T00:00:00Z"));
+        assert_eq!(result.duration.as_deref(), Some("2:21"));
+        assert_eq!(result.thumbnail.as_deref(), Some("https://img.example/m.jpg"));
+    }
+
+    #[tokio::test]
+    async fn dropped_vertical_items_still_count_towards_the_next_offset() {
+        let backend = vertical_backend("/news.js", serde_json::json!({
+            "results": [
+                {"title": "One", "url": "https://news.example/1"},
+                {"title": "No link"},
+                {"title": "Two", "url": "https://news.example/2"},
+            ],
+            "next": "news.js?s=3",
+        })).await;
+
+        let query = SearchQuery { vertical: Vertical::News, ..mock::query(2) };
+        let page = backend.search(&reqwest::Client::new(), &query).await.unwrap();
+
+        assert_eq!(page.results.len(), 2);
+        assert_eq!(page.next_offset, Some(3));
+    }
+}
+
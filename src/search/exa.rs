@@ -0,0 +1,196 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{check_status, env_or, required_env, unsupported, SearchBackend, SearchError, SearchPage, SearchQuery, SearchResult, Vertical};
+
+const DEFAULT_ENDPOINT: &str = "https://api.exa.ai";
+const MAX_RESULTS: usize = 100;
+
+/// Exa neural search API. Requires `EXA_API_KEY`.
+pub struct ExaBackend {
+    endpoint: String,
+    api_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExaResponse {
+    #[serde(default)]
+    results: Vec<ExaResult>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExaResult {
+    title: Option<String>,
+    url: String,
+    text: Option<String>,
+    published_date: Option<String>,
+    author: Option<String>,
+    image: Option<String>,
+}
+
+impl ExaBackend {
+    pub fn new(endpoint: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into(), api_key: api_key.into() }
+    }
+
+    pub fn from_env() -> Result<Self, SearchError> {
+        Ok(Self::new(env_or("EXA_ENDPOINT", DEFAULT_ENDPOINT), required_env("EXA_API_KEY")?))
+    }
+}
+
+#[async_trait]
+impl SearchBackend for ExaBackend {
+    fn name(&self) -> &'static str {
+        "exa"
+    }
+
+    async fn fetch_page(&self, client: &reqwest::Client, query: &SearchQuery, offset: usize, want: usize) -> Result<SearchPage, SearchError> {
+        // Exa has no offset parameter: ask for everything up to the end of
+        // the requested window and drop the part already seen.
+        if !matches!(query.vertical, Vertical::Web | Vertical::News) {
+            return Err(unsupported(self.name(), query.vertical));
+        }
+
+        let num_results = (offset + want).min(MAX_RESULTS);
+        if offset >= num_results {
+            return Ok(SearchPage::default());
+        }
+
+        let filters = &query.filters;
+        let mut request = json!({
+            "query": query.query,
+            "numResults": num_results,
+            "contents": {"text": {"maxCharacters": 300}}
+        });
+
+        if query.vertical == Vertical::News {
+            request["category"] = json!("news");
+        }
+
+        // Exa filters domains and dates natively; it has no safe-search or
+        // language switch, and region only biases ranking via `userLocation`.
+        if !filters.sites.is_empty() {
+            request["includeDomains"] = json!(filters.sites);
+        }
+        if !filters.exclude_sites.is_empty() {
+            request["excludeDomains"] = json!(filters.exclude_sites);
+        }
+        if let Some(range) = filters.time_range {
+            let since = chrono::Utc::now() - chrono::Duration::days(range.days());
+            request["startPublishedDate"] = json!(since.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string());
+        }
+        if let Some(region) = &filters.region {
+            request["userLocation"] = json!(region.to_uppercase());
+        }
+
+        let response = client.post(format!("{}/search", self.endpoint.trim_end_matches('/')))
+            .header("x-api-key", &self.api_key)
+            .json(&request)
+            .send()
+            .await?;
+
+        let body: ExaResponse = check_status(self.name(), response)?.json().await?;
+
+        let exhausted = body.results.len() < num_results || num_results == MAX_RESULTS;
+        let results: Vec<SearchResult> = body.results
+            .into_iter()
+            .skip(offset)
+            .map(|r| SearchResult {
+                title: r.title.unwrap_or_else(|| "Untitled".to_string()),
+                url: r.url,
+                snippet: r.text.unwrap_or_default().trim().to_string(),
+                publisher: r.author,
+                published: r.published_date,
+                thumbnail: r.image,
+                ..Default::default()
+            })
+            .collect();
+        let next_offset = (!exhausted).then_some(offset + results.len());
+
+        Ok(SearchPage { results, next_offset, ..Default::default() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::mock;
+    use axum::http::HeaderMap;
+    use axum::routing::post;
+
+    /// Returns exactly `numResults` results, numbered from zero.
+    async fn search(headers: HeaderMap, axum::Json(body): axum::Json<serde_json::Value>) -> axum::Json<serde_json::Value> {
+        assert_eq!(headers["x-api-key"], "key");
+        let count = body["numResults"].as_u64().unwrap();
+        let results: Vec<_> = (0..count)
+            .map(|n| json!({
+                "title": format!("r{}", n),
+                "url": format!("https://example.com/{}", n),
+                "text": "  snippet  ",
+                "publishedDate": "2024-01-02",
+                "author": "Ann",
+            }))
+            .collect();
+        axum::Json(json!({ "results": results }))
+    }
+
+    async fn backend() -> ExaBackend {
+        let base = mock::serve(axum::Router::new().route("/search", post(search))).await;
+        ExaBackend::new(base, "key")
+    }
+
+    #[tokio::test]
+    async fn parses_results() {
+        let page = backend().await.search(&reqwest::Client::new(), &mock::query(2)).await.unwrap();
+
+        assert_eq!(page.results.len(), 2);
+        let first = &page.results[0];
+        assert_eq!((first.title.as_str(), first.snippet.as_str()), ("r0", "snippet"));
+        assert_eq!(first.publisher.as_deref(), Some("Ann"));
+        assert_eq!(page.next_offset, Some(2));
+    }
+
+    #[tokio::test]
+    async fn skips_results_before_the_offset() {
+        let query = SearchQuery { offset: 3, ..mock::query(2) };
+
+        let page = backend().await.search(&reqwest::Client::new(), &query).await.unwrap();
+
+        let titles: Vec<&str> = page.results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["r3", "r4"]);
+        assert_eq!(page.next_offset, Some(5));
+    }
+
+    #[tokio::test]
+    async fn too_many_requests_is_blocked() {
+        let base = mock::serve(axum::Router::new().route("/search", post(|| async { mock::too_many_requests() }))).await;
+
+        let error = ExaBackend::new(base, "key").search(&reqwest::Client::new(), &mock::query(5)).await.err();
+        assert!(matches!(error, Some(SearchError::Blocked { backend: "exa", retry_after_secs: 30, .. })));
+    }
+
+    #[tokio::test]
+    async fn news_uses_the_news_category() {
+        let base = mock::serve(axum::Router::new().route("/search", post(|axum::Json(body): axum::Json<serde_json::Value>| async move {
+            assert_eq!(body["category"], "news");
+            axum::Json(json!({
+                "results": [{
+                    "title": "Headline", "url": "https://news.example/a", "text": "Summary",
+                    "publishedDate": "2024-03-04", "author": "Ann", "image": "https://img.example/a.jpg",
+                }]
+            }))
+        }))).await;
+
+        let query = SearchQuery { vertical: Vertical::News, ..mock::query(5) };
+        let page = ExaBackend::new(base, "key").search(&reqwest::Client::new(), &query).await.unwrap();
+
+        let result = &page.results[0];
+        assert_eq!((result.title.as_str(), result.url.as_str(), result.snippet.as_str()), ("Headline", "https://news.example/a", "Summary"));
+        assert_eq!(result.publisher.as_deref(), Some("Ann"));
+        assert_eq!(result.published.as_deref(), Some("2024-03-04"));
+        assert_eq!(result.thumbnail.as_deref(), Some("https://img.example/a.jpg"));
+    }
+}
+
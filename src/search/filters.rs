@@ -0,0 +1,188 @@
+//! Optional search filters shared by all backends. Each backend maps these
+//! onto its native parameters and falls back to query operators (`site:`)
+//! where it has no native equivalent.
+
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeRange {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl TimeRange {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimeRange::Day => "day",
+            TimeRange::Week => "week",
+            TimeRange::Month => "month",
+            TimeRange::Year => "year",
+        }
+    }
+
+    /// Single-letter form (`d`, `w`, `m`, `y`) used by several engines.
+    pub fn letter(self) -> &'static str {
+        &self.as_str()[..1]
+    }
+
+    pub fn days(self) -> i64 {
+        match self {
+            TimeRange::Day => 1,
+            TimeRange::Week => 7,
+            TimeRange::Month => 31,
+            TimeRange::Year => 365,
+        }
+    }
+}
+
+impl FromStr for TimeRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "day" | "d" | "24h" => Ok(TimeRange::Day),
+            "week" | "w" => Ok(TimeRange::Week),
+            "month" | "m" => Ok(TimeRange::Month),
+            "year" | "y" => Ok(TimeRange::Year),
+            other => Err(format!("❌ Invalid time_range: '{}'. Use one of: day, week, month, year.", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SafeSearch {
+    Off,
+    Moderate,
+    Strict,
+}
+
+impl FromStr for SafeSearch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" | "false" | "none" => Ok(SafeSearch::Off),
+            "moderate" | "medium" => Ok(SafeSearch::Moderate),
+            "strict" | "true" | "on" => Ok(SafeSearch::Strict),
+            other => Err(format!("❌ Invalid safe_search: '{}'. Use one of: off, moderate, strict.", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilters {
+    /// Restrict results to these domains.
+    pub sites: Vec<String>,
+    /// Drop results from these domains.
+    pub exclude_sites: Vec<String>,
+    pub time_range: Option<TimeRange>,
+    /// Two-letter country code, lowercase (e.g. `us`, `de`).
+    pub region: Option<String>,
+    /// Two-letter language code, lowercase (e.g. `en`, `de`).
+    pub language: Option<String>,
+    pub safe_search: Option<SafeSearch>,
+}
+
+impl SearchFilters {
+    /// Reduce `https://docs.rs/foo/` style input to a bare `docs.rs/foo`.
+    pub fn normalize_site(site: &str) -> String {
+        let site = site.trim();
+        let site = site.split_once("://").map_or(site, |(_, rest)| rest);
+        site.trim_end_matches('/').to_lowercase()
+    }
+
+    /// `site:` / `-site:` operators emulating the site filters, for
+    /// backends without native domain restriction.
+    pub fn site_operators(&self) -> String {
+        let mut parts = Vec::new();
+
+        match self.sites.len() {
+            0 => {}
+            1 => parts.push(format!("site:{}", self.sites[0])),
+            _ => parts.push(format!(
+                "({})",
+                self.sites.iter().map(|s| format!("site:{}", s)).collect::<Vec<_>>().join(" OR ")
+            )),
+        }
+
+        parts.extend(self.exclude_sites.iter().map(|s| format!("-site:{}", s)));
+        parts.join(" ")
+    }
+
+    /// Language to pair with `region` when the caller gave only a region:
+    /// the country's main language, or `None` for regions not listed.
+    pub fn language_or_default(&self) -> Option<String> {
+        self.language.clone().or_else(|| {
+            let region = self.region.as_deref()?;
+            REGION_LANGUAGES.iter()
+                .find(|(code, _)| *code == region)
+                .map(|(_, language)| language.to_string())
+        })
+    }
+}
+
+/// Main language (ISO 639-1) of each country code (ISO 3166-1, plus `uk`).
+const REGION_LANGUAGES: &[(&str, &str)] = &[
+    ("ar", "es"), ("at", "de"), ("au", "en"), ("be", "nl"), ("bg", "bg"), ("br", "pt"),
+    ("ca", "en"), ("ch", "de"), ("cl", "es"), ("cn", "zh"), ("co", "es"), ("cz", "cs"),
+    ("de", "de"), ("dk", "da"), ("ee", "et"), ("es", "es"), ("fi", "fi"), ("fr", "fr"),
+    ("gb", "en"), ("gr", "el"), ("hk", "zh"), ("hr", "hr"), ("hu", "hu"), ("id", "id"),
+    ("ie", "en"), ("il", "he"), ("in", "en"), ("it", "it"), ("jp", "ja"), ("kr", "ko"),
+    ("lt", "lt"), ("lv", "lv"), ("mx", "es"), ("my", "ms"), ("nl", "nl"), ("no", "nb"),
+    ("nz", "en"), ("pe", "es"), ("ph", "en"), ("pl", "pl"), ("pt", "pt"), ("ro", "ro"),
+    ("ru", "ru"), ("sa", "ar"), ("se", "sv"), ("sg", "en"), ("si", "sl"), ("sk", "sk"),
+    ("th", "th"), ("tr", "tr"), ("tw", "zh"), ("ua", "uk"), ("uk", "en"), ("us", "en"),
+    ("vn", "vi"), ("za", "en"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(region: &str) -> SearchFilters {
+        SearchFilters { region: Some(region.to_string()), ..SearchFilters::default() }
+    }
+
+    #[test]
+    fn regions_default_to_their_language() {
+        assert_eq!(region("gb").language_or_default().as_deref(), Some("en"));
+        assert_eq!(region("jp").language_or_default().as_deref(), Some("ja"));
+        assert_eq!(region("se").language_or_default().as_deref(), Some("sv"));
+        assert_eq!(region("br").language_or_default().as_deref(), Some("pt"));
+        assert_eq!(region("dk").language_or_default().as_deref(), Some("da"));
+        assert_eq!(region("xx").language_or_default(), None);
+        assert_eq!(SearchFilters::default().language_or_default(), None);
+
+        let explicit = SearchFilters { language: Some("fr".to_string()), ..region("ch") };
+        assert_eq!(explicit.language_or_default().as_deref(), Some("fr"));
+    }
+
+    #[test]
+    fn time_ranges_parse_and_map() {
+        assert_eq!("24h".parse::<TimeRange>(), Ok(TimeRange::Day));
+        assert_eq!(" Week ".parse::<TimeRange>(), Ok(TimeRange::Week));
+        assert!("decade".parse::<TimeRange>().is_err());
+
+        assert_eq!(TimeRange::Month.as_str(), "month");
+        assert_eq!(TimeRange::Month.letter(), "m");
+        assert_eq!(TimeRange::Year.days(), 365);
+    }
+
+    #[test]
+    fn site_restrictions_become_query_operators() {
+        assert_eq!(SearchFilters::normalize_site(" https://Docs.rs/foo/ "), "docs.rs/foo");
+
+        let one = SearchFilters { sites: vec!["docs.rs".to_string()], ..SearchFilters::default() };
+        assert_eq!(one.site_operators(), "site:docs.rs");
+
+        let several = SearchFilters {
+            sites: vec!["docs.rs".to_string(), "crates.io".to_string()],
+            exclude_sites: vec!["spam.example".to_string()],
+            ..SearchFilters::default()
+        };
+        assert_eq!(several.site_operators(), "(site:docs.rs OR site:crates.io) -site:spam.example");
+        assert_eq!(SearchFilters::default().site_operators(), "");
+    }
+}
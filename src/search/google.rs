@@ -0,0 +1,221 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{check_status, env_or, required_env, unsupported, SafeSearch, SearchBackend, SearchError, SearchPage, SearchQuery, SearchResult, Vertical};
+
+const DEFAULT_ENDPOINT: &str = "https://www.googleapis.com/customsearch/v1";
+const MAX_RESULTS: usize = 100;
+
+/// Google Programmable (Custom) Search JSON API.
+/// Requires `GOOGLE_API_KEY` and `GOOGLE_CSE_ID`.
+pub struct GoogleBackend {
+    endpoint: String,
+    api_key: String,
+    engine_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleResponse {
+    #[serde(default)]
+    items: Vec<GoogleResult>,
+    queries: Option<GoogleQueries>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleQueries {
+    #[serde(default)]
+    next_page: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleResult {
+    #[serde(default)]
+    title: String,
+    /// Web: the page. Image search: the image itself.
+    link: String,
+    #[serde(default)]
+    snippet: String,
+    #[serde(rename = "displayLink")]
+    display_link: Option<String>,
+    image: Option<GoogleImage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleImage {
+    context_link: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    thumbnail_link: Option<String>,
+}
+
+impl From<GoogleResult> for SearchResult {
+    fn from(r: GoogleResult) -> Self {
+        match r.image {
+            Some(image) => SearchResult {
+                title: r.title,
+                url: image.context_link.unwrap_or_else(|| r.link.clone()),
+                snippet: r.snippet,
+                publisher: r.display_link,
+                thumbnail: image.thumbnail_link,
+                image_url: Some(r.link),
+                width: image.width,
+                height: image.height,
+                ..Default::default()
+            },
+            None => SearchResult { title: r.title, url: r.link, snippet: r.snippet, ..Default::default() },
+        }
+    }
+}
+
+impl GoogleBackend {
+    pub fn new(endpoint: impl Into<String>, api_key: impl Into<String>, engine_id: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into(), api_key: api_key.into(), engine_id: engine_id.into() }
+    }
+
+    pub fn from_env() -> Result<Self, SearchError> {
+        Ok(Self::new(
+            env_or("GOOGLE_ENDPOINT", DEFAULT_ENDPOINT),
+            required_env("GOOGLE_API_KEY")?,
+            required_env("GOOGLE_CSE_ID")?,
+        ))
+    }
+}
+
+#[async_trait]
+impl SearchBackend for GoogleBackend {
+    fn name(&self) -> &'static str {
+        "google"
+    }
+
+    async fn fetch_page(&self, client: &reqwest::Client, query: &SearchQuery, offset: usize, want: usize) -> Result<SearchPage, SearchError> {
+        if !matches!(query.vertical, Vertical::Web | Vertical::Images) {
+            return Err(unsupported(self.name(), query.vertical));
+        }
+
+        // At most 10 items per request, and `start + num` may not exceed 100
+        if offset >= MAX_RESULTS {
+            return Ok(SearchPage::default());
+        }
+        let filters = &query.filters;
+
+        let mut params = vec![
+            ("key", self.api_key.clone()),
+            ("cx", self.engine_id.clone()),
+            ("num", want.clamp(1, 10).min(MAX_RESULTS - offset).to_string()),
+            ("start", (offset + 1).to_string()),
+        ];
+        if query.vertical == Vertical::Images {
+            params.push(("searchType", "image".to_string()));
+        }
+
+        // `siteSearch` takes a single domain; anything more complex falls
+        // back to query operators
+        match (filters.sites.as_slice(), filters.exclude_sites.as_slice()) {
+            ([site], []) => params.extend([
+                ("q", query.query.clone()),
+                ("siteSearch", site.clone()),
+                ("siteSearchFilter", "i".to_string()),
+            ]),
+            ([], [site]) => params.extend([
+                ("q", query.query.clone()),
+                ("siteSearch", site.clone()),
+                ("siteSearchFilter", "e".to_string()),
+            ]),
+            _ => params.push(("q", query.query_with_site_operators())),
+        }
+        if let Some(range) = filters.time_range {
+            params.push(("dateRestrict", format!("{}1", range.letter())));
+        }
+        if let Some(region) = &filters.region {
+            params.push(("gl", region.clone()));
+        }
+        if let Some(language) = &filters.language {
+            params.push(("lr", format!("lang_{}", language)));
+            params.push(("hl", language.clone()));
+        }
+        if let Some(safe) = filters.safe_search {
+            // Google only distinguishes "active" and "off"
+            let level = if safe == SafeSearch::Off { "off" } else { "active" };
+            params.push(("safe", level.to_string()));
+        }
+
+        let response = client.get(&self.endpoint)
+            .query(&params)
+            .send()
+            .await?;
+
+        let body: GoogleResponse = check_status(self.name(), response)?.json().await?;
+
+        let more = body.queries.is_some_and(|q| !q.next_page.is_empty());
+        let results: Vec<SearchResult> = body.items
+            .into_iter()
+            .map(SearchResult::from)
+            .collect();
+        let next_offset = more.then_some(offset + results.len());
+
+        Ok(SearchPage { results, next_offset, ..Default::default() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::mock;
+    use axum::extract::Query;
+    use axum::routing::get;
+    use std::collections::HashMap;
+
+    /// Fifteen results in total, `num` at a time from the 1-based `start`.
+    async fn custom_search(Query(params): Query<HashMap<String, String>>) -> axum::Json<serde_json::Value> {
+        assert_eq!((params["key"].as_str(), params["cx"].as_str()), ("key", "cse"));
+        let start: usize = params["start"].parse().unwrap();
+        let num: usize = params["num"].parse().unwrap();
+        let end = (start - 1 + num).min(15);
+        let items: Vec<_> = (start - 1..end)
+            .map(|n| serde_json::json!({
+                "title": format!("r{}", n),
+                "link": format!("https://example.com/{}", n),
+                "snippet": "snippet",
+            }))
+            .collect();
+        let next_page = if end < 15 { vec![serde_json::json!({"startIndex": end + 1})] } else { vec![] };
+        axum::Json(serde_json::json!({ "items": items, "queries": {"nextPage": next_page} }))
+    }
+
+    async fn backend() -> GoogleBackend {
+        let base = mock::serve(axum::Router::new().route("/", get(custom_search))).await;
+        GoogleBackend::new(format!("{}/", base), "key", "cse")
+    }
+
+    #[tokio::test]
+    async fn parses_results() {
+        let page = backend().await.search(&reqwest::Client::new(), &mock::query(2)).await.unwrap();
+
+        let titles: Vec<&str> = page.results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["r0", "r1"]);
+        assert_eq!(page.results[1].url, "https://example.com/1");
+        assert_eq!(page.next_offset, Some(2));
+    }
+
+    #[tokio::test]
+    async fn pages_in_steps_of_ten() {
+        let page = backend().await.search(&reqwest::Client::new(), &mock::query(20)).await.unwrap();
+
+        assert_eq!(page.results.len(), 15);
+        assert_eq!(page.results[14].title, "r14");
+        assert_eq!(page.next_offset, None);
+    }
+
+    #[tokio::test]
+    async fn too_many_requests_is_blocked() {
+        let base = mock::serve(axum::Router::new().route("/", get(|| async { mock::too_many_requests() }))).await;
+
+        let error = GoogleBackend::new(format!("{}/", base), "key", "cse")
+            .search(&reqwest::Client::new(), &mock::query(5))
+            .await
+            .err();
+        assert!(matches!(error, Some(SearchError::Blocked { backend: "google", retry_after_secs: 30, .. })));
+    }
+}
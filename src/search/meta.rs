@@ -0,0 +1,245 @@
+use async_trait::async_trait;
+use futures::future::join_all;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::urls::canonical_key;
+use super::{backend_by_name, SearchBackend, SearchError, SearchPage, SearchQuery, SearchResult};
+
+/// Constant from the original reciprocal rank fusion paper; dampens the
+/// advantage of top ranks so agreement across engines dominates.
+const RRF_K: f64 = 60.0;
+const DEFAULT_ENGINE_TIMEOUT_SECS: u64 = 10;
+/// Every backend `metasearch` will try when `SEARCH_BACKENDS` is unset.
+const ALL_BACKENDS: &[&str] = &["duckduckgo", "searxng", "brave", "exa", "bing", "google"];
+
+/// Queries several backends concurrently and merges their rankings with
+/// reciprocal rank fusion. Engines that fail or time out are reported as
+/// warnings instead of failing the whole search.
+pub struct MetaSearchBackend {
+    engines: Vec<Box<dyn SearchBackend>>,
+    timeout: Duration,
+}
+
+impl MetaSearchBackend {
+    pub fn new(engines: Vec<Box<dyn SearchBackend>>, timeout: Duration) -> Self {
+        Self { engines, timeout }
+    }
+
+    /// Engines come from the comma-separated `SEARCH_BACKENDS` list. Without
+    /// it, every backend whose credentials are present in the environment is
+    /// used. `SEARCH_ENGINE_TIMEOUT_SECS` bounds each engine.
+    pub fn from_env() -> Result<Self, SearchError> {
+        let engines: Vec<Box<dyn SearchBackend>> = match std::env::var("SEARCH_BACKENDS") {
+            Ok(list) if !list.trim().is_empty() => list
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| match name {
+                    "meta" | "metasearch" => Err(SearchError::Config("SEARCH_BACKENDS cannot include 'meta'".to_string())),
+                    _ => backend_by_name(name),
+                })
+                .collect::<Result<_, _>>()?,
+            _ => ALL_BACKENDS
+                .iter()
+                .filter_map(|name| backend_by_name(name).ok())
+                .collect(),
+        };
+
+        if engines.is_empty() {
+            return Err(SearchError::Config("metasearch has no usable engines".to_string()));
+        }
+
+        let timeout = std::env::var("SEARCH_ENGINE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_ENGINE_TIMEOUT_SECS);
+
+        Ok(Self::new(engines, Duration::from_secs(timeout)))
+    }
+
+    /// Merge per-engine rankings. Results are deduplicated by canonical URL;
+    /// the first engine to return a result supplies its fields, later ones
+    /// only fill gaps.
+    fn fuse(rankings: Vec<(&'static str, Vec<SearchResult>)>) -> Vec<SearchResult> {
+        let mut merged: Vec<(f64, SearchResult)> = Vec::new();
+        let mut index_by_key: HashMap<String, usize> = HashMap::new();
+
+        for (engine, results) in rankings {
+            for (rank, result) in results.into_iter().enumerate() {
+                let score = 1.0 / (RRF_K + rank as f64 + 1.0);
+                let key = canonical_key(result.image_url.as_deref().unwrap_or(&result.url));
+
+                match index_by_key.get(&key) {
+                    Some(&i) => {
+                        let (total, existing) = &mut merged[i];
+                        if existing.engines.contains(&engine.to_string()) {
+                            continue;
+                        }
+                        *total += score;
+                        existing.engines.push(engine.to_string());
+                        if existing.snippet.is_empty() {
+                            existing.snippet = result.snippet;
+                        }
+                        existing.publisher = existing.publisher.take().or(result.publisher);
+                        existing.published = existing.published.take().or(result.published);
+                        existing.thumbnail = existing.thumbnail.take().or(result.thumbnail);
+                    }
+                    None => {
+                        let mut result = result;
+                        result.engines = vec![engine.to_string()];
+                        index_by_key.insert(key, merged.len());
+                        merged.push((score, result));
+                    }
+                }
+            }
+        }
+
+        // Stable sort keeps first-seen order between equal scores
+        merged.sort_by(|a, b| b.0.total_cmp(&a.0));
+        merged.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+#[async_trait]
+impl SearchBackend for MetaSearchBackend {
+    fn name(&self) -> &'static str {
+        "meta"
+    }
+
+    /// Rank fusion needs whole rankings, so every engine is asked for the
+    /// top `offset + want` results and the fused list is sliced afterwards.
+    async fn fetch_page(&self, client: &reqwest::Client, query: &SearchQuery, offset: usize, want: usize) -> Result<SearchPage, SearchError> {
+        let engine_query = SearchQuery {
+            limit: offset + want,
+            offset: 0,
+            ..query.clone()
+        };
+
+        let outcomes = join_all(self.engines.iter().map(|engine| {
+            let engine_query = &engine_query;
+            async move {
+                let outcome = tokio::time::timeout(self.timeout, engine.search(client, engine_query)).await;
+                (engine.name(), outcome)
+            }
+        }))
+        .await;
+
+        let mut rankings = Vec::new();
+        let mut warnings = Vec::new();
+        let mut first_error = None;
+        let mut any_engine_has_more = false;
+
+        for (name, outcome) in outcomes {
+            match outcome {
+                Ok(Ok(page)) => {
+                    any_engine_has_more |= page.next_offset.is_some();
+                    warnings.extend(page.warnings);
+                    rankings.push((name, page.results));
+                }
+                Ok(Err(e)) => {
+                    tracing::warn!("Metasearch engine {} failed: {}", name, e);
+                    warnings.push(format!("{} failed: {}", name, e.to_string().lines().next().unwrap_or_default()));
+                    first_error.get_or_insert(e);
+                }
+                Err(_) => {
+                    tracing::warn!("Metasearch engine {} timed out after {:?}", name, self.timeout);
+                    warnings.push(format!("{} timed out after {}s", name, self.timeout.as_secs()));
+                }
+            }
+        }
+
+        if rankings.is_empty() {
+            return Err(first_error.unwrap_or_else(|| SearchError::Network("all search engines timed out".to_string())));
+        }
+
+        let fused = Self::fuse(rankings);
+        let has_more = fused.len() > offset + want || any_engine_has_more;
+        let results: Vec<SearchResult> = fused.into_iter().skip(offset).take(want).collect();
+        let next_offset = (has_more && !results.is_empty()).then_some(offset + results.len());
+
+        Ok(SearchPage { results, next_offset, warnings })
+    }
+
+    /// Engines already paginate internally, so one fused window suffices.
+    async fn search(&self, client: &reqwest::Client, query: &SearchQuery) -> Result<SearchPage, SearchError> {
+        self.fetch_page(client, query, query.offset, query.limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{mock, BraveBackend, SearxngBackend};
+    use axum::routing::get;
+    use serde_json::json;
+
+    fn searxng_results(urls: &[&str]) -> axum::Json<serde_json::Value> {
+        let results: Vec<_> = urls.iter().map(|url| json!({"title": url, "url": url, "content": "from searxng"})).collect();
+        axum::Json(json!({ "results": results }))
+    }
+
+    fn brave_results(urls: &[&str]) -> axum::Json<serde_json::Value> {
+        let results: Vec<_> = urls.iter().map(|url| json!({"title": url, "url": url, "description": ""})).collect();
+        axum::Json(json!({ "web": {"results": results} }))
+    }
+
+    async fn meta(router: axum::Router) -> MetaSearchBackend {
+        let base = mock::serve(router).await;
+        MetaSearchBackend::new(
+            vec![Box::new(SearxngBackend::new(&base)), Box::new(BraveBackend::new(&base, "key"))],
+            Duration::from_secs(5),
+        )
+    }
+
+    #[tokio::test]
+    async fn fuses_rankings_and_merges_duplicates() {
+        let backend = meta(axum::Router::new()
+            .route("/search", get(|| async { searxng_results(&["https://a.example/", "https://b.example/"]) }))
+            .route("/web/search", get(|| async { brave_results(&["https://www.b.example", "https://c.example/"]) }))).await;
+
+        let page = backend.search(&reqwest::Client::new(), &mock::query(10)).await.unwrap();
+
+        let urls: Vec<&str> = page.results.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, ["https://b.example/", "https://a.example/", "https://c.example/"]);
+        assert_eq!(page.results[0].engines, ["searxng", "brave"]);
+        assert!(page.warnings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn pages_through_the_fused_ranking() {
+        let backend = meta(axum::Router::new()
+            .route("/search", get(|| async { searxng_results(&["https://a.example/", "https://b.example/"]) }))
+            .route("/web/search", get(|| async { brave_results(&["https://b.example/", "https://c.example/"]) }))).await;
+        let query = SearchQuery { offset: 1, ..mock::query(1) };
+
+        let page = backend.search(&reqwest::Client::new(), &query).await.unwrap();
+
+        assert_eq!(page.results.len(), 1);
+        assert_eq!(page.results[0].url, "https://a.example/");
+        assert_eq!(page.next_offset, Some(2));
+    }
+
+    #[tokio::test]
+    async fn a_failing_engine_becomes_a_warning() {
+        let backend = meta(axum::Router::new()
+            .route("/search", get(|| async { mock::too_many_requests() }))
+            .route("/web/search", get(|| async { brave_results(&["https://c.example/"]) }))).await;
+
+        let page = backend.search(&reqwest::Client::new(), &mock::query(10)).await.unwrap();
+
+        assert_eq!(page.results.len(), 1);
+        assert_eq!(page.warnings.len(), 1);
+        assert!(page.warnings[0].starts_with("searxng failed"), "{}", page.warnings[0]);
+    }
+
+    #[tokio::test]
+    async fn fails_when_every_engine_fails() {
+        let backend = meta(axum::Router::new()
+            .route("/search", get(|| async { mock::too_many_requests() }))
+            .route("/web/search", get(|| async { mock::too_many_requests() }))).await;
+
+        let error = backend.search(&reqwest::Client::new(), &mock::query(10)).await.err();
+        assert!(matches!(error, Some(SearchError::Blocked { backend: "searxng", .. })));
+    }
+}
@@ -0,0 +1,36 @@
+//! Local HTTP server for exercising backends against canned responses.
+
+use axum::Router;
+
+use super::{SearchFilters, SearchQuery, Vertical};
+
+/// Serve `router` on an ephemeral localhost port and return its base URL.
+/// The server lives until the test's runtime shuts down.
+pub async fn serve(router: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind mock server");
+    let addr = listener.local_addr().expect("mock server address");
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.expect("mock server");
+    });
+    format!("http://{}", addr)
+}
+
+/// A plain web query for `rust` asking for `limit` results.
+pub fn query(limit: usize) -> SearchQuery {
+    SearchQuery {
+        query: "rust".to_string(),
+        limit,
+        offset: 0,
+        filters: SearchFilters::default(),
+        vertical: Vertical::Web,
+    }
+}
+
+/// A response the backends must report as rate limiting.
+pub fn too_many_requests() -> axum::response::Response {
+    axum::response::IntoResponse::into_response((
+        axum::http::StatusCode::TOO_MANY_REQUESTS,
+        [("Retry-After", "30")],
+        "slow down",
+    ))
+}
@@ -0,0 +1,380 @@
+//! Pluggable web search backends for the `search` tool.
+//!
+//! The active backend is selected with the `SEARCH_BACKEND` environment
+//! variable. Each backend reads its API key (and optionally an endpoint
+//! override, handy for pointing at a local mock server) from the environment.
+
+use async_trait::async_trait;
+use std::str::FromStr;
+use thiserror::Error;
+
+mod bing;
+mod brave;
+mod duckduckgo;
+mod exa;
+mod filters;
+mod google;
+mod meta;
+#[cfg(test)]
+pub mod mock;
+mod searxng;
+pub mod urls;
+
+pub use bing::BingBackend;
+pub use brave::BraveBackend;
+pub use duckduckgo::DuckDuckGoBackend;
+pub use exa::ExaBackend;
+pub use filters::{SafeSearch, SearchFilters, TimeRange};
+pub use google::GoogleBackend;
+pub use meta::MetaSearchBackend;
+pub use searxng::SearxngBackend;
+
+/// Upper bound on backend round-trips for a single `search` call.
+const MAX_PAGES_PER_SEARCH: usize = 5;
+
+/// A single search request as understood by every backend.
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub query: String,
+    pub limit: usize,
+    /// Zero-based index of the first result to return.
+    pub offset: usize,
+    pub filters: SearchFilters,
+    pub vertical: Vertical,
+}
+
+/// Which kind of results to search for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Vertical {
+    #[default]
+    Web,
+    News,
+    Images,
+    Videos,
+}
+
+impl Vertical {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Vertical::Web => "web",
+            Vertical::News => "news",
+            Vertical::Images => "images",
+            Vertical::Videos => "videos",
+        }
+    }
+}
+
+impl FromStr for Vertical {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "web" | "general" => Ok(Vertical::Web),
+            "news" => Ok(Vertical::News),
+            "images" | "image" => Ok(Vertical::Images),
+            "videos" | "video" => Ok(Vertical::Videos),
+            other => Err(format!("❌ Invalid search type: '{}'. Use one of: web, news, images, videos.", other)),
+        }
+    }
+}
+
+impl SearchQuery {
+    /// The query text with site filters appended as `site:` operators.
+    pub fn query_with_site_operators(&self) -> String {
+        let operators = self.filters.site_operators();
+        if operators.is_empty() {
+            self.query.clone()
+        } else {
+            format!("{} {}", self.query, operators)
+        }
+    }
+}
+
+/// A single search hit, normalized across backends.
+///
+/// `url` is always the page the hit links to (for images, the page the
+/// image was found on). The optional fields are filled in by the news,
+/// image and video verticals when the backend provides them.
+#[derive(Debug, Clone, Default)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+    pub publisher: Option<String>,
+    pub published: Option<String>,
+    pub thumbnail: Option<String>,
+    /// Direct link to the full-size image (images vertical).
+    pub image_url: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Video running time as reported by the backend.
+    pub duration: Option<String>,
+    /// Engines that returned this result (metasearch only).
+    pub engines: Vec<String>,
+}
+
+/// A batch of results plus where to continue from, if anything is left.
+#[derive(Debug, Clone, Default)]
+pub struct SearchPage {
+    pub results: Vec<SearchResult>,
+    /// Result offset of the next unseen result, `None` when exhausted.
+    pub next_offset: Option<usize>,
+    /// Non-fatal problems worth showing the model, e.g. an engine that
+    /// timed out during a metasearch.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum SearchError {
+    #[error("Network error while searching: {0}")]
+    Network(String),
+    #[error("❌ Search request failed with status: {0}\n\nThis might be a temporary issue. Please try again later.")]
+    Status(reqwest::StatusCode),
+    #[error("Error reading search response: {0}")]
+    Parse(String),
+    #[error("❌ Search backend misconfigured: {0}")]
+    Config(String),
+    #[error("🚫 Search blocked by {backend}: {reason}.\n\nThe search engine is rate-limiting or challenging this server; this does not mean there are no results, so rephrasing the query will not help. Retry in about {retry_after_secs} seconds.")]
+    Blocked { backend: &'static str, reason: String, retry_after_secs: u64 },
+    #[error("❌ The {backend} search backend does not support {vertical} search.\n\nTry type \"web\" or configure a backend that supports this vertical.")]
+    UnsupportedVertical { backend: &'static str, vertical: &'static str },
+}
+
+impl From<reqwest::Error> for SearchError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            SearchError::Parse(e.to_string())
+        } else {
+            SearchError::Network(e.to_string())
+        }
+    }
+}
+
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Short identifier used in config and logs.
+    fn name(&self) -> &'static str;
+
+    /// Fetch one native page of results starting at result `offset`.
+    /// `want` is a hint for how many results the caller still needs.
+    async fn fetch_page(&self, client: &reqwest::Client, query: &SearchQuery, offset: usize, want: usize) -> Result<SearchPage, SearchError>;
+
+    /// Collect results from consecutive pages until `query.limit` unique
+    /// results are gathered or the backend runs out. Duplicates are dropped
+    /// as they arrive, so they neither count towards the limit nor shift the
+    /// continuation offset. Errors after the first page end the search early
+    /// with what was gathered so far.
+    async fn search(&self, client: &reqwest::Client, query: &SearchQuery) -> Result<SearchPage, SearchError> {
+        let mut results = Vec::new();
+        let mut warnings = Vec::new();
+        let mut deduplicator = urls::Deduplicator::default();
+        let mut next_offset = Some(query.offset);
+
+        for page_index in 0..MAX_PAGES_PER_SEARCH {
+            let Some(offset) = next_offset else { break };
+            let want = query.limit - results.len();
+
+            let page = match self.fetch_page(client, query, offset, want).await {
+                Ok(page) => page,
+                Err(e) if page_index == 0 => return Err(e),
+                Err(e) => {
+                    tracing::warn!("{} page at offset {} failed, returning partial results: {}", self.name(), offset, e);
+                    break;
+                }
+            };
+            warnings.extend(page.warnings);
+
+            let fetched = page.results.len();
+            let mut consumed = 0;
+            for result in page.results {
+                if results.len() == query.limit {
+                    break;
+                }
+                consumed += 1;
+                results.extend(deduplicator.admit(result));
+            }
+
+            if results.len() == query.limit {
+                next_offset = if consumed < fetched { Some(offset + consumed) } else { page.next_offset };
+                break;
+            }
+            next_offset = if fetched == 0 { None } else { page.next_offset };
+        }
+
+        Ok(SearchPage { results, next_offset, warnings })
+    }
+}
+
+/// Opaque token handed to the model so it can ask for the next batch.
+pub fn continuation_token(backend: &str, offset: usize) -> String {
+    format!("{}:{}", backend, offset)
+}
+
+/// Recover the result offset from a token produced by `continuation_token`
+/// for `backend`. Offsets mean nothing to another backend, so a token
+/// naming a different one is rejected; a bare offset is accepted.
+pub fn parse_continuation_token(token: &str, backend: &str) -> Option<usize> {
+    let offset = match token.rsplit_once(':') {
+        Some((prefix, offset)) if prefix.trim().eq_ignore_ascii_case(backend) => offset,
+        Some(_) => return None,
+        None => token,
+    };
+    offset.trim().parse().ok()
+}
+
+/// Build the backend named by `SEARCH_BACKEND` (default: `duckduckgo`).
+pub fn backend_from_env() -> Result<Box<dyn SearchBackend>, SearchError> {
+    let name = std::env::var("SEARCH_BACKEND").unwrap_or_else(|_| "duckduckgo".to_string());
+    backend_by_name(&name)
+}
+
+pub fn backend_by_name(name: &str) -> Result<Box<dyn SearchBackend>, SearchError> {
+    match name.trim().to_lowercase().as_str() {
+        "duckduckgo" | "ddg" => Ok(Box::new(DuckDuckGoBackend::from_env())),
+        "searxng" => Ok(Box::new(SearxngBackend::from_env()?)),
+        "brave" => Ok(Box::new(BraveBackend::from_env()?)),
+        "exa" => Ok(Box::new(ExaBackend::from_env()?)),
+        "bing" => Ok(Box::new(BingBackend::from_env()?)),
+        "google" => Ok(Box::new(GoogleBackend::from_env()?)),
+        "meta" | "metasearch" => Ok(Box::new(MetaSearchBackend::from_env()?)),
+        other => Err(SearchError::Config(format!("unknown search backend '{}'", other))),
+    }
+}
+
+fn env_or(key: &str, default: &str) -> String {
+    std::env::var(key)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| default.to_string())
+}
+
+fn required_env(key: &str) -> Result<String, SearchError> {
+    std::env::var(key)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| SearchError::Config(format!("{} is not set", key)))
+}
+
+fn unsupported(backend: &'static str, vertical: Vertical) -> SearchError {
+    SearchError::UnsupportedVertical { backend, vertical: vertical.as_str() }
+}
+
+/// Used when a block page gives no `Retry-After` hint.
+const DEFAULT_BLOCK_RETRY_SECS: u64 = 60;
+
+/// Build a `SearchError::Blocked` and count it in the metrics.
+fn blocked(backend: &'static str, reason: impl Into<String>, retry_after_secs: Option<u64>) -> SearchError {
+    crate::metrics::record_search_blocked(backend);
+    SearchError::Blocked {
+        backend,
+        reason: reason.into(),
+        retry_after_secs: retry_after_secs.unwrap_or(DEFAULT_BLOCK_RETRY_SECS),
+    }
+}
+
+/// Turn a non-2xx response into a `SearchError`. Rate limiting is reported
+/// as `Blocked` so the caller retries later instead of rephrasing.
+fn check_status(backend: &'static str, response: reqwest::Response) -> Result<reqwest::Response, SearchError> {
+    let status = response.status();
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response.headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok());
+        return Err(blocked(backend, "HTTP 429 Too Many Requests", retry_after));
+    }
+
+    if status.is_success() {
+        Ok(response)
+    } else {
+        Err(SearchError::Status(status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serves `urls` three at a time, with `next_offset` set while any are left.
+    struct FakeBackend {
+        urls: Vec<&'static str>,
+    }
+
+    #[async_trait]
+    impl SearchBackend for FakeBackend {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        async fn fetch_page(&self, _client: &reqwest::Client, _query: &SearchQuery, offset: usize, _want: usize) -> Result<SearchPage, SearchError> {
+            let end = (offset + 3).min(self.urls.len());
+            let results = self.urls[offset.min(end)..end]
+                .iter()
+                .map(|url| SearchResult { url: url.to_string(), ..Default::default() })
+                .collect();
+            Ok(SearchPage { results, next_offset: (end < self.urls.len()).then_some(end), ..Default::default() })
+        }
+    }
+
+    fn urls(page: &SearchPage) -> Vec<&str> {
+        page.results.iter().map(|r| r.url.as_str()).collect()
+    }
+
+    #[test]
+    fn continuation_tokens_round_trip() {
+        assert_eq!(parse_continuation_token(&continuation_token("brave", 40), "brave"), Some(40));
+        assert_eq!(parse_continuation_token("15", "brave"), Some(15));
+        assert_eq!(parse_continuation_token("brave:", "brave"), None);
+        assert_eq!(parse_continuation_token("brave:-1", "brave"), None);
+    }
+
+    #[test]
+    fn continuation_tokens_from_another_backend_are_rejected() {
+        assert_eq!(parse_continuation_token(&continuation_token("brave", 40), "exa"), None);
+        assert_eq!(parse_continuation_token("duckduckgo:30", "searxng"), None);
+    }
+
+    #[tokio::test]
+    async fn a_partly_used_page_continues_after_the_last_result_taken() {
+        let backend = FakeBackend { urls: vec!["https://a.example/", "https://b.example/", "https://c.example/", "https://d.example/"] };
+        let query = SearchQuery { offset: 1, ..mock::query(1) };
+
+        let page = backend.search(&reqwest::Client::new(), &query).await.unwrap();
+
+        assert_eq!(urls(&page), ["https://b.example/"]);
+        assert_eq!(page.next_offset, Some(2));
+    }
+
+    #[tokio::test]
+    async fn a_fully_used_page_continues_at_the_backend_offset() {
+        let backend = FakeBackend { urls: vec!["https://a.example/", "https://b.example/", "https://c.example/", "https://d.example/"] };
+
+        let page = backend.search(&reqwest::Client::new(), &mock::query(3)).await.unwrap();
+
+        assert_eq!(urls(&page).len(), 3);
+        assert_eq!(page.next_offset, Some(3));
+    }
+
+    #[tokio::test]
+    async fn duplicates_do_not_count_towards_the_limit() {
+        let backend = FakeBackend {
+            urls: vec!["https://a.example/", "https://www.a.example/", "https://b.example/", "https://a.example/?utm_source=x", "https://c.example/", "https://d.example/"],
+        };
+
+        let page = backend.search(&reqwest::Client::new(), &mock::query(3)).await.unwrap();
+
+        assert_eq!(urls(&page), ["https://a.example/", "https://b.example/", "https://c.example/"]);
+        // The continuation skips every raw result consumed, duplicates included
+        assert_eq!(page.next_offset, Some(5));
+    }
+
+    #[tokio::test]
+    async fn stops_when_the_backend_runs_out() {
+        let backend = FakeBackend { urls: vec!["https://a.example/", "https://a.example", "https://b.example/"] };
+
+        let page = backend.search(&reqwest::Client::new(), &mock::query(5)).await.unwrap();
+
+        assert_eq!(urls(&page), ["https://a.example/", "https://b.example/"]);
+        assert_eq!(page.next_offset, None);
+    }
+}
@@ -0,0 +1,225 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{check_status, required_env, SafeSearch, SearchBackend, SearchError, SearchPage, SearchQuery, SearchResult, Vertical};
+
+/// SearXNG merges several engines, so the number of results per `pageno`
+/// varies. Only the first `PAGE_SIZE` of each page are used so that result
+/// offsets map onto page numbers predictably.
+const PAGE_SIZE: usize = 10;
+
+/// Queries a self-hosted SearXNG instance through its JSON API.
+/// The instance must have the `json` format enabled.
+pub struct SearxngBackend {
+    endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearxngResponse {
+    #[serde(default)]
+    results: Vec<SearxngResult>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearxngResult {
+    #[serde(default)]
+    title: String,
+    url: String,
+    #[serde(default)]
+    content: String,
+    published_date: Option<String>,
+    thumbnail: Option<String>,
+    #[serde(rename = "thumbnail_src")]
+    thumbnail_src: Option<String>,
+    #[serde(rename = "img_src")]
+    img_src: Option<String>,
+    /// Image size as `"<width> x <height>"`.
+    resolution: Option<String>,
+    /// Video length, e.g. `"4:12"`.
+    length: Option<String>,
+    author: Option<String>,
+    source: Option<String>,
+    engine: Option<String>,
+}
+
+impl From<SearxngResult> for SearchResult {
+    fn from(r: SearxngResult) -> Self {
+        let (width, height) = r.resolution
+            .as_deref()
+            .and_then(|res| res.split_once('x'))
+            .map(|(w, h)| (w.trim().parse().ok(), h.trim().parse().ok()))
+            .unwrap_or_default();
+
+        SearchResult {
+            title: r.title,
+            url: r.url,
+            snippet: r.content,
+            publisher: r.author.or(r.source).or(r.engine),
+            published: r.published_date,
+            thumbnail: r.thumbnail_src.or(r.thumbnail),
+            image_url: r.img_src,
+            width,
+            height,
+            duration: r.length,
+            ..Default::default()
+        }
+    }
+}
+
+impl SearxngBackend {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into() }
+    }
+
+    pub fn from_env() -> Result<Self, SearchError> {
+        Ok(Self::new(required_env("SEARXNG_URL")?))
+    }
+
+    fn search_url(&self) -> String {
+        format!("{}/search", self.endpoint.trim_end_matches('/'))
+    }
+}
+
+#[async_trait]
+impl SearchBackend for SearxngBackend {
+    fn name(&self) -> &'static str {
+        "searxng"
+    }
+
+    async fn fetch_page(&self, client: &reqwest::Client, query: &SearchQuery, offset: usize, _want: usize) -> Result<SearchPage, SearchError> {
+        let skip = offset % PAGE_SIZE;
+        let filters = &query.filters;
+
+        let mut params = vec![
+            ("q", query.query_with_site_operators()),
+            ("format", "json".to_string()),
+            ("pageno", (offset / PAGE_SIZE + 1).to_string()),
+        ];
+        let category = match query.vertical {
+            Vertical::Web => "general",
+            Vertical::News => "news",
+            Vertical::Images => "images",
+            Vertical::Videos => "videos",
+        };
+        params.push(("categories", category.to_string()));
+        if let Some(range) = filters.time_range {
+            params.push(("time_range", range.as_str().to_string()));
+        }
+        match (&filters.language, &filters.region) {
+            (Some(language), Some(region)) => params.push(("language", format!("{}-{}", language, region.to_uppercase()))),
+            (Some(language), None) => params.push(("language", language.clone())),
+            (None, Some(_)) => params.extend(filters.language_or_default().map(|l| ("language", l))),
+            (None, None) => {}
+        }
+        if let Some(safe) = filters.safe_search {
+            let level = match safe {
+                SafeSearch::Off => "0",
+                SafeSearch::Moderate => "1",
+                SafeSearch::Strict => "2",
+            };
+            params.push(("safesearch", level.to_string()));
+        }
+
+        let response = client.get(self.search_url())
+            .query(&params)
+            .header("Accept", "application/json")
+            .send()
+            .await?;
+
+        let body: SearxngResponse = check_status(self.name(), response)?.json().await?;
+
+        let results: Vec<SearchResult> = body.results
+            .into_iter()
+            .take(PAGE_SIZE)
+            .skip(skip)
+            .map(SearchResult::from)
+            .collect();
+        let next_offset = (!results.is_empty()).then_some(offset + results.len());
+
+        Ok(SearchPage { results, next_offset, ..Default::default() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::mock;
+    use axum::extract::Query;
+    use axum::routing::get;
+    use std::collections::HashMap;
+
+    /// Twelve results per page, titled `p<pageno>-<n>`.
+    async fn search_page(Query(params): Query<HashMap<String, String>>) -> axum::Json<serde_json::Value> {
+        assert_eq!(params["format"], "json");
+        let page = &params["pageno"];
+        let results: Vec<_> = (0..12)
+            .map(|n| serde_json::json!({
+                "title": format!("p{}-{}", page, n),
+                "url": format!("https://example.com/{}/{}", page, n),
+                "content": "snippet",
+                "publishedDate": "2024-01-02",
+                "engine": "wikipedia",
+            }))
+            .collect();
+        axum::Json(serde_json::json!({ "results": results }))
+    }
+
+    #[tokio::test]
+    async fn parses_results() {
+        let base = mock::serve(axum::Router::new().route("/search", get(search_page))).await;
+
+        let page = SearxngBackend::new(base).search(&reqwest::Client::new(), &mock::query(2)).await.unwrap();
+
+        assert_eq!(page.results.len(), 2);
+        let first = &page.results[0];
+        assert_eq!((first.title.as_str(), first.url.as_str()), ("p1-0", "https://example.com/1/0"));
+        assert_eq!(first.published.as_deref(), Some("2024-01-02"));
+        assert_eq!(first.publisher.as_deref(), Some("wikipedia"));
+        assert_eq!(page.next_offset, Some(2));
+    }
+
+    #[tokio::test]
+    async fn offsets_map_onto_page_numbers() {
+        let base = mock::serve(axum::Router::new().route("/search", get(search_page))).await;
+        let query = SearchQuery { offset: 8, ..mock::query(4) };
+
+        let page = SearxngBackend::new(base).search(&reqwest::Client::new(), &query).await.unwrap();
+
+        let titles: Vec<&str> = page.results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["p1-8", "p1-9", "p2-0", "p2-1"]);
+        assert_eq!(page.next_offset, Some(12));
+    }
+
+    #[tokio::test]
+    async fn too_many_requests_is_blocked() {
+        let base = mock::serve(axum::Router::new().route("/search", get(|| async { mock::too_many_requests() }))).await;
+
+        let error = SearxngBackend::new(base).search(&reqwest::Client::new(), &mock::query(5)).await.err();
+        assert!(matches!(error, Some(SearchError::Blocked { backend: "searxng", retry_after_secs: 30, .. })));
+    }
+
+    #[tokio::test]
+    async fn news_is_mapped() {
+        let base = mock::serve(axum::Router::new().route("/search", get(|Query(params): Query<HashMap<String, String>>| async move {
+            assert_eq!(params["categories"], "news");
+            axum::Json(serde_json::json!({
+                "results": [{
+                    "title": "Headline", "url": "https://news.example/a", "content": "Summary",
+                    "publishedDate": "2024-03-04T05:06:07", "source": "Example News", "engine": "bing news",
+                    "thumbnail": "https://img.example/a.jpg",
+                }]
+            }))
+        }))).await;
+
+        let query = SearchQuery { vertical: Vertical::News, ..mock::query(5) };
+        let page = SearxngBackend::new(base).search(&reqwest::Client::new(), &query).await.unwrap();
+
+        let result = &page.results[0];
+        assert_eq!((result.title.as_str(), result.url.as_str(), result.snippet.as_str()), ("Headline", "https://news.example/a", "Summary"));
+        assert_eq!(result.publisher.as_deref(), Some("Example News"));
+        assert_eq!(result.published.as_deref(), Some("2024-03-04T05:06:07"));
+        assert_eq!(result.thumbnail.as_deref(), Some("https://img.example/a.jpg"));
+    }
+}
+
@@ -0,0 +1,179 @@
+//! URL cleanup for search results: redirect unwrapping, absolutizing,
+//! tracking-parameter removal and canonical-URL deduplication.
+
+use std::collections::HashSet;
+use url::Url;
+
+use super::SearchResult;
+
+/// Query parameters that only exist to track clicks.
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "gclsrc", "dclid", "msclkid", "yclid", "twclid", "igshid",
+    "mc_cid", "mc_eid", "_hsenc", "_hsmi", "mkt_tok", "oly_anon_id", "oly_enc_id",
+    "vero_id", "wickedid", "rb_clickid", "s_cid", "ref_src",
+];
+
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name.as_str())
+}
+
+/// Resolve `href` against `base`, unwrap DuckDuckGo `/l/?uddg=` redirects
+/// and strip tracking parameters. Returns `None` for non-HTTP(S) links.
+pub fn normalize_url(href: &str, base: &Url) -> Option<String> {
+    let href = href.trim();
+    if href.is_empty() {
+        return None;
+    }
+
+    let mut url = base.join(href).ok()?;
+
+    if let Some(target) = unwrap_redirect(&url) {
+        url = target;
+    }
+
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return None;
+    }
+
+    strip_tracking_params(&mut url);
+    Some(url.to_string())
+}
+
+/// Follow the `uddg` target of a DuckDuckGo click-tracking link.
+fn unwrap_redirect(url: &Url) -> Option<Url> {
+    let host = url.host_str()?;
+    if !(host == "duckduckgo.com" || host.ends_with(".duckduckgo.com")) || !url.path().starts_with("/l/") {
+        return None;
+    }
+
+    url.query_pairs()
+        .find(|(key, _)| key == "uddg")
+        .and_then(|(_, target)| Url::parse(&target).ok())
+}
+
+fn strip_tracking_params(url: &mut Url) {
+    if url.query().is_none() {
+        return;
+    }
+
+    let kept: Vec<(String, String)> = url.query_pairs()
+        .filter(|(key, _)| !is_tracking_param(key))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+}
+
+/// Key used to decide whether two URLs point at the same document:
+/// scheme, `www.`, fragment, trailing slash and parameter order are ignored.
+pub fn canonical_key(url: &str) -> String {
+    let Ok(parsed) = Url::parse(url) else {
+        return url.trim().to_lowercase();
+    };
+
+    let host = parsed.host_str().unwrap_or("").to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+
+    let mut params: Vec<(String, String)> = parsed.query_pairs()
+        .filter(|(key, _)| !is_tracking_param(key))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    params.sort();
+
+    let mut key = match parsed.port() {
+        Some(port) => format!("{}:{}{}", host, port, parsed.path().trim_end_matches('/')),
+        None => format!("{}{}", host, parsed.path().trim_end_matches('/')),
+    };
+    for (i, (k, v)) in params.iter().enumerate() {
+        key.push(if i == 0 { '?' } else { '&' });
+        key.push_str(&format!("{}={}", k, v));
+    }
+    key
+}
+
+/// Strips tracking parameters from results and drops later duplicates of
+/// the same canonical URL, keeping the first (best ranked) occurrence.
+/// Image results are keyed on the image itself, since one page can host
+/// many distinct images.
+#[derive(Debug, Default)]
+pub struct Deduplicator {
+    seen: HashSet<String>,
+}
+
+impl Deduplicator {
+    /// `result` cleaned up, or `None` if an earlier result was the same document.
+    pub fn admit(&mut self, mut result: SearchResult) -> Option<SearchResult> {
+        if let Ok(mut url) = Url::parse(&result.url) {
+            strip_tracking_params(&mut url);
+            result.url = url.to_string();
+        }
+        let key = canonical_key(result.image_url.as_deref().unwrap_or(&result.url));
+        self.seen.insert(key).then_some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Url {
+        Url::parse("https://html.duckduckgo.com/html/").unwrap()
+    }
+
+    #[test]
+    fn unwraps_duckduckgo_redirects() {
+        let href = "//duckduckgo.com/l/?uddg=https%3A%2F%2Fexample.com%2Fpage%3Fid%3D1%26utm_source%3Dddg&rut=abc";
+        assert_eq!(normalize_url(href, &base()).as_deref(), Some("https://example.com/page?id=1"));
+    }
+
+    #[test]
+    fn resolves_relative_links_and_rejects_other_schemes() {
+        assert_eq!(normalize_url("/about", &base()).as_deref(), Some("https://html.duckduckgo.com/about"));
+        assert_eq!(normalize_url("javascript:void(0)", &base()), None);
+        assert_eq!(normalize_url("  ", &base()), None);
+    }
+
+    #[test]
+    fn strips_only_tracking_params() {
+        let href = "https://example.com/?q=rust&UTM_Medium=x&fbclid=y&page=2";
+        assert_eq!(normalize_url(href, &base()).as_deref(), Some("https://example.com/?q=rust&page=2"));
+        assert_eq!(normalize_url("https://example.com/?gclid=1", &base()).as_deref(), Some("https://example.com/"));
+    }
+
+    #[test]
+    fn canonical_key_ignores_cosmetic_differences() {
+        let key = canonical_key("https://example.com/docs?a=1&b=2");
+        assert_eq!(canonical_key("http://www.example.com/docs/?b=2&a=1#intro"), key);
+        assert_eq!(canonical_key("https://EXAMPLE.com/docs?a=1&b=2&utm_source=x"), key);
+        assert_ne!(canonical_key("https://example.com/docs?a=2&b=2"), key);
+        assert_ne!(canonical_key("https://example.com:8080/docs?a=1&b=2"), key);
+    }
+
+    fn result(url: &str, image_url: Option<&str>) -> SearchResult {
+        SearchResult { url: url.to_string(), image_url: image_url.map(str::to_string), ..Default::default() }
+    }
+
+    #[test]
+    fn deduplicator_keeps_the_first_occurrence() {
+        let mut deduplicator = Deduplicator::default();
+
+        let first = deduplicator.admit(result("https://example.com/a?utm_source=x", None)).unwrap();
+        assert_eq!(first.url, "https://example.com/a");
+        assert!(deduplicator.admit(result("https://www.example.com/a/", None)).is_none());
+        assert!(deduplicator.admit(result("https://example.com/b", None)).is_some());
+    }
+
+    #[test]
+    fn deduplicator_keys_images_on_the_image() {
+        let mut deduplicator = Deduplicator::default();
+
+        assert!(deduplicator.admit(result("https://example.com/gallery", Some("https://cdn.example/1.jpg"))).is_some());
+        assert!(deduplicator.admit(result("https://example.com/gallery", Some("https://cdn.example/2.jpg"))).is_some());
+        assert!(deduplicator.admit(result("https://other.example/", Some("https://cdn.example/1.jpg"))).is_none());
+    }
+}
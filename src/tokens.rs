@@ -0,0 +1,119 @@
+//! Token counting with the o200k_harmony BPE, the tokenizer of the gpt-oss
+//! models, so page budgets are measured in what the model actually reads.
+//! The vocabulary is compiled into the binary; nothing is downloaded.
+
+use tiktoken_rs::{o200k_harmony_singleton, CoreBPE};
+
+/// Token budget for one `open` response, overridable with `MAX_TOKENS`
+/// (`0` disables the limit).
+const DEFAULT_MAX_TOKENS: usize = 10_000;
+
+fn bpe() -> &'static CoreBPE {
+    o200k_harmony_singleton()
+}
+
+pub fn count(text: &str) -> usize {
+    bpe().encode_ordinary(text).len()
+}
+
+/// Server-wide budget for `open` when the call does not give one.
+pub fn default_max_tokens() -> Option<usize> {
+    let budget = std::env::var("MAX_TOKENS")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_TOKENS);
+    (budget > 0).then_some(budget)
+}
+
+/// How many of `lines` fit in `budget` tokens, and the tokens they use.
+/// At least one line is always taken so a reader can make progress past a
+/// line longer than the whole budget.
+pub fn fit_lines<S: AsRef<str>>(lines: &[S], budget: usize) -> (usize, usize) {
+    match (fit_within(lines, budget), lines.first()) {
+        ((0, _), Some(first)) => (1, count(first.as_ref())),
+        (fit, _) => fit,
+    }
+}
+
+/// Like `fit_lines`, but never over `budget`: for a share of a budget
+/// other consumers are counting on, nothing is taken if the first line
+/// does not fit.
+pub fn fit_within<S: AsRef<str>>(lines: &[S], budget: usize) -> (usize, usize) {
+    let mut used = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let tokens = count(line.as_ref());
+        if used + tokens > budget {
+            return (i, used);
+        }
+        used += tokens;
+    }
+    (lines.len(), used)
+}
+
+/// Split `budget` between consumers that want `needs`: everyone gets an
+/// equal share, and what a small consumer leaves unused goes to the rest.
+pub fn split_budget(needs: &[usize], budget: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..needs.len()).collect();
+    order.sort_by_key(|&i| needs[i]);
+
+    let mut shares = vec![0; needs.len()];
+    let mut remaining = budget;
+    for (k, &i) in order.iter().enumerate() {
+        let fair = remaining / (order.len() - k);
+        shares[i] = needs[i].min(fair);
+        remaining -= shares[i];
+    }
+    shares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_harmony_tokens() {
+        assert_eq!(count(""), 0);
+        assert!(count("hello world") >= 2);
+        assert!(count(&"word ".repeat(100)) >= 100);
+    }
+
+    #[test]
+    fn fit_lines_stops_before_the_budget_is_exceeded() {
+        let lines = ["alpha beta", "gamma delta", "epsilon zeta"];
+        let first = count(lines[0]);
+        let two = first + count(lines[1]);
+
+        assert_eq!(fit_lines(&lines, two), (2, two));
+        assert_eq!(fit_lines(&lines, two - 1), (1, first));
+        assert_eq!(fit_lines(&lines, usize::MAX), (3, two + count(lines[2])));
+        assert_eq!(fit_lines::<&str>(&[], 10), (0, 0));
+    }
+
+    #[test]
+    fn fit_lines_always_takes_one_line() {
+        let long = "word ".repeat(50);
+        assert_eq!(fit_lines(&[long.as_str(), "x"], 1), (1, count(&long)));
+    }
+
+    #[test]
+    fn fit_within_never_exceeds_the_budget() {
+        let long = "word ".repeat(50);
+        assert_eq!(fit_within(&[long.as_str(), "x"], 1), (0, 0));
+        assert_eq!(fit_within(&["x"], 0), (0, 0));
+        assert_eq!(fit_within(&["x", "y"], count("x")), (1, count("x")));
+    }
+
+    #[test]
+    fn split_budget_shares_equally_and_passes_on_leftovers() {
+        assert_eq!(split_budget(&[100, 100, 100], 90), vec![30, 30, 30]);
+        // The small consumer takes what it needs; the rest is split evenly.
+        assert_eq!(split_budget(&[10, 100, 100], 90), vec![10, 40, 40]);
+        assert_eq!(split_budget(&[5, 7], 100), vec![5, 7]);
+        // Shares never add up to more than the budget, even when some are 0.
+        let shares = split_budget(&[50, 50, 50], 2);
+        assert_eq!(shares.iter().sum::<usize>(), 2);
+        assert!(shares.contains(&0));
+        assert!(split_budget(&[], 10).is_empty());
+    }
+}
+